@@ -1,7 +1,12 @@
-use crate::{app::data::write::tweet, utils::TweetData};
+use crate::{
+    search::{respell, SearchOptions, SearchQuery},
+    utils::{SearchResult, TrackedAccountData, TweetData, TweetReferencesData, UserSnapshotData},
+};
 use futures::{executor::block_on, future::join_all};
 use sea_orm::DatabaseConnection;
+use std::collections::HashSet;
 use super::seed;
+use twitter_v2::data::ReferencedTweetKind;
 use twitter_v2::{Tweet, User};
 
 pub mod data;
@@ -17,49 +22,240 @@ pub async fn load_users_tweets_from_twitter_handle(
     //     let tweets = data::read::tweets(db).await;
     //     TweetData::from_vec_tweet(&user, tweets)
     // }
-    let user = load_user_from_twitter_handle(db, twitter_handle).await;
+    let user = match load_user_from_twitter_handle(db, twitter_handle).await {
+        Some(user) => user,
+        None => return Vec::new(),
+    };
+    // the handle we were given may be one the user has since renamed away from
+    let twitter_handle = user.username.as_str();
     let user_tweets = data::read::users_tweets(db, twitter_handle).await;
-    //need something better
+    // archived tweets are shown as they are, checking for newer ones is left to
+    // `sync_users_tweets` so nothing waits on the server when there's no need to
     if user_tweets.len() == 0 {
-        let user_tweets = server::get_tweets_from_twitter_handle(twitter_handle);
-        data::write::tweets(db, &user_tweets).await;
+        sync_users_tweets(db, &user).await;
+        TweetData::from_vec_tweet(&user, data::read::users_tweets(db, twitter_handle).await)
+    } else {
         TweetData::from_vec_tweet(&user, user_tweets)
+    }
+}
+
+/// Archives the user's tweets that are newer than their latest archived one,
+/// or all of them when none are archived yet. Returns whether there were any.
+pub async fn sync_users_tweets(db: &DatabaseConnection, user: &User) -> bool {
+    let twitter_handle = user.username.as_str();
+    let new_tweets = match data::read::latest_tweet_from_user_by_twitter_handle(db, twitter_handle)
+        .await
+    {
+        None => server::get_tweets_from_twitter_handle(twitter_handle),
+        Some(_) => match has_new_tweets(db, twitter_handle).await {
+            Ok(true) => load_users_new_tweets(db, twitter_handle).await,
+            Ok(false) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        },
+    };
+    let new_tweets = match new_tweets {
+        Ok(new_tweets) => new_tweets,
+        Err(e) => {
+            println!("Failed to sync @{} because {}", twitter_handle, e);
+            return false;
+        }
+    };
+
+    if new_tweets.is_empty() {
+        println!("No new tweets to add for @{}", twitter_handle);
     } else {
-        if has_new_tweets(db, twitter_handle).await {
-            println!("Adding new tweets");
-            let new_tweets = load_users_new_tweets(db, twitter_handle).await;
-            println!("{:?}", &new_tweets);
-            data::write::tweets(db, &new_tweets).await;
-            TweetData::from_vec_tweet(&user, data::read::users_tweets(db, twitter_handle).await)
-        } else {
-            println!("No new tweets to add");
-            TweetData::from_vec_tweet(&user, user_tweets)
+        println!("Adding {} new tweets for @{}", new_tweets.len(), twitter_handle);
+        archive_tweets(db, &new_tweets).await;
+    }
+    data::write::tracked_account_synced(
+        db,
+        user.id
+            .as_u64()
+            .try_into()
+            .expect("Failed to parse u64 into i64"),
+    )
+    .await;
+    !new_tweets.is_empty()
+}
+
+/// Syncs every tracked account's profile and tweets, returning the accounts
+/// that had new tweets.
+pub async fn sync_tracked_accounts(db: &DatabaseConnection) -> Vec<User> {
+    let mut users_with_new_tweets = Vec::new();
+    for tracked_account in load_tracked_accounts(db).await {
+        // a renamed account is only found under its new handle, so its tweets
+        // are synced under whatever the refreshed profile says
+        let user = refresh_user(db, &tracked_account.user).await;
+        if sync_users_tweets(db, &user).await {
+            users_with_new_tweets.push(user);
+        }
+    }
+    users_with_new_tweets
+}
+
+/// How many hops of replied to, quoted and retweeted tweets are fetched beyond
+/// the tweets being archived. Every missing tweet costs a request, so long
+/// reply chains are cut off here rather than followed to their root.
+const REFERENCED_TWEET_FETCH_DEPTH: usize = 2;
+
+/// Loads the authors of the tweets that aren't archived yet, so their tweets
+/// can be written. Authors that can't be loaded are left out, and so are their
+/// tweets.
+async fn archive_authors(db: &DatabaseConnection, tweets: &Vec<Tweet>) {
+    let author_ids: HashSet<i64> = tweets
+        .iter()
+        .filter_map(|tweet| tweet.author_id)
+        .map(|author_id| {
+            author_id
+                .as_u64()
+                .try_into()
+                .expect("Failed to parse i64 from u64")
+        })
+        .collect();
+    for author_id in author_ids {
+        load_user_from_id(db, author_id).await;
+    }
+}
+
+/// Archives the tweets with their references, then fetches the tweets they
+/// reference that aren't archived yet, up to `REFERENCED_TWEET_FETCH_DEPTH`
+/// hops away.
+async fn archive_tweets(db: &DatabaseConnection, tweets: &Vec<Tweet>) {
+    let mut batch = tweets.clone();
+    let mut requested_tweet_ids: HashSet<i64> = HashSet::new();
+    for depth in 0..=REFERENCED_TWEET_FETCH_DEPTH {
+        let label = if depth == 0 { "tweets" } else { "referenced tweets" };
+        archive_authors(db, &batch).await;
+        match data::write::tweets(db, &batch).await {
+            Ok(report) => {
+                println!("Archived {} {}: {}", batch.len(), label, report);
+                for tweet in &report.without_author {
+                    println!("Skipped tweet {} as its author couldn't be loaded", tweet.id);
+                }
+            }
+            Err(e) => println!("Failed to archive {} {} because {}", batch.len(), label, e),
+        }
+        if depth == REFERENCED_TWEET_FETCH_DEPTH {
+            break;
+        }
+
+        let mut missing_tweet_ids = Vec::new();
+        let referenced_tweets = batch
+            .iter()
+            .flat_map(|tweet| tweet.referenced_tweets.iter().flatten());
+        for referenced_tweet in referenced_tweets {
+            let referenced_tweet_id: i64 = referenced_tweet
+                .id
+                .as_u64()
+                .try_into()
+                .expect("Failed to parse i64 from u64");
+            if requested_tweet_ids.insert(referenced_tweet_id)
+                && !data::read::does_tweet_exist(db, referenced_tweet_id).await
+            {
+                missing_tweet_ids.push(referenced_tweet_id);
+            }
+        }
+        batch = missing_tweet_ids
+            .into_iter()
+            .filter_map(|tweet_id| server::get_tweet_by_id(tweet_id).ok().flatten())
+            .collect();
+        if batch.is_empty() {
+            break;
         }
     }
 }
 
-pub async fn load_user_from_id(db: &DatabaseConnection, id: i64) -> User {
+/// The archived user, fetched from the server when they aren't archived yet.
+/// `None` when they aren't archived and can't be fetched, like when offline.
+pub async fn load_user_from_id(db: &DatabaseConnection, id: i64) -> Option<User> {
     match data::read::user_by_id(db, id).await {
-        Some(user) => user,
+        Some(user) => Some(user),
         None => {
             println!("Loading user of ID {} from the server", id);
-            let user = server::get_user_by_id(id.try_into().expect("Failed to parse i64 from u64"));
-            data::write::user(db, &user).await;
-            user
+            match server::get_user_by_id(id.try_into().expect("Failed to parse i64 from u64")) {
+                Ok(user) => {
+                    archive_user(db, &user).await;
+                    Some(user)
+                }
+                Err(e) => {
+                    println!("Failed to load user of ID {} because {}", id, e);
+                    None
+                }
+            }
         }
     }
 }
 
-pub async fn load_user_from_twitter_handle(db: &DatabaseConnection, twitter_handle: &str) -> User {
+pub async fn load_user_from_twitter_handle(
+    db: &DatabaseConnection,
+    twitter_handle: &str,
+) -> Option<User> {
     match data::read::user_by_twitter_handle(db, twitter_handle).await {
-        Some(user) => user,
+        Some(user) => Some(user),
         None => {
             println!("Loading user @{} from the server", twitter_handle);
-            let user = server::get_user_by_twitter_handle(twitter_handle);
-            data::write::user(db, &user).await;
-            user
+            match server::get_user_by_twitter_handle(twitter_handle) {
+                Ok(user) => {
+                    archive_user(db, &user).await;
+                    Some(user)
+                }
+                Err(e) => {
+                    println!("Failed to load user @{} because {}", twitter_handle, e);
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Fetches the user's current profile and archives it, which records a profile
+/// snapshot when anything changed since the last one. The archived profile is
+/// kept when the server can't be asked.
+pub async fn refresh_user(db: &DatabaseConnection, user: &User) -> User {
+    match server::get_user_by_id(user.id.as_u64()) {
+        Ok(current_user) => {
+            archive_user(db, &current_user).await;
+            current_user
+        }
+        Err(e) => {
+            println!("Failed to refresh @{}'s profile because {}", user.username, e);
+            user.clone()
+        }
+    }
+}
+
+/// Stores a user fetched from the server. An account we already have under
+/// another handle has renamed, so its archived profile is updated in place and
+/// the new handle is added to its aliases, which `refresh_user` catches on
+/// every sync of a tracked account.
+async fn archive_user(db: &DatabaseConnection, user: &User) {
+    let id: i64 = user
+        .id
+        .as_u64()
+        .try_into()
+        .expect("Failed to parse u64 into i64");
+    if let Some(archived_user) = data::read::user_by_id(db, id).await {
+        if archived_user.username != user.username {
+            println!(
+                "@{} is now known as @{}",
+                archived_user.username, user.username
+            );
         }
     }
+    data::write::user(db, user).await;
+    data::write::user_alias(db, user).await;
+    data::write::user_snapshot(db, user).await;
+}
+
+pub async fn load_profile_history(db: &DatabaseConnection, user: &User) -> Vec<UserSnapshotData> {
+    data::read::user_snapshots(
+        db,
+        user.id
+            .as_u64()
+            .try_into()
+            .expect("Failed to parse u64 into i64"),
+    )
+    .await
 }
 
 pub async fn load_conversation_from_tweet_id(
@@ -78,9 +274,19 @@ pub async fn load_conversation_from_tweet_id(
             "Loading conversation starting with tweet {} from Server",
             tweet_id
         );
-        let conversation = server::get_conversation_by_tweet_id(tweet_id);
-        data::write::tweets(db, &conversation).await;
-        vec_tweet_data_from_vec_tweet(db, conversation).await
+        match server::get_conversation_by_tweet_id(tweet_id) {
+            Ok(conversation) => {
+                archive_tweets(db, &conversation).await;
+                vec_tweet_data_from_vec_tweet(db, conversation).await
+            }
+            Err(e) => {
+                println!(
+                    "Showing only the archived part of the conversation starting with tweet {} because {}",
+                    tweet_id, e
+                );
+                vec_tweet_data_from_vec_tweet(db, conversation).await
+            }
+        }
     }
 }
 
@@ -96,7 +302,10 @@ pub async fn tweet_data_from_tweet(db: &DatabaseConnection, tweet: Tweet) -> Twe
     )
     .await;
 
-    TweetData::new(&user, tweet)
+    match user {
+        Some(user) => TweetData::new(&user, tweet),
+        None => TweetData::with_missing_author(tweet),
+    }
 }
 
 pub async fn vec_tweet_data_from_vec_tweet(
@@ -112,26 +321,64 @@ pub async fn vec_tweet_data_from_vec_tweet(
     future_tweets.await
 }
 
+/// Everything archived that the tweet replies to, quotes or retweets, and every
+/// archived reply, quote and retweet of it.
+pub async fn load_tweet_references(db: &DatabaseConnection, tweet_data: &TweetData) -> TweetReferencesData {
+    let tweet_id: i64 = tweet_data
+        .tweet
+        .id
+        .as_u64()
+        .try_into()
+        .expect("Failed to parse u64 into i64");
+
+    let mut references = TweetReferencesData {
+        replies: vec_tweet_data_from_vec_tweet(db, data::read::replies_to_tweet(db, tweet_id).await)
+            .await,
+        quotes: vec_tweet_data_from_vec_tweet(db, data::read::quotes_of_tweet(db, tweet_id).await)
+            .await,
+        retweets: vec_tweet_data_from_vec_tweet(db, data::read::retweets_of_tweet(db, tweet_id).await)
+            .await,
+        ..TweetReferencesData::default()
+    };
+
+    for (reference_type, tweet) in data::read::referenced_tweets(db, tweet_id).await {
+        let referenced_tweet = Some(tweet_data_from_tweet(db, tweet).await);
+        match reference_type {
+            ReferencedTweetKind::RepliedTo => references.replying_to = referenced_tweet,
+            ReferencedTweetKind::Quoted => references.quoting = referenced_tweet,
+            ReferencedTweetKind::Retweeted => references.retweet_of = referenced_tweet,
+        }
+    }
+    references
+}
+
 pub async fn load_tweet_from_id(db: &DatabaseConnection, tweet_id: i64, user_id: i64) -> Option<TweetData> {
-    let user = load_user_from_id(db, user_id).await;
+    let user = load_user_from_id(db, user_id).await?;
     match data::read::tweet_by_id(db, tweet_id).await {
         Some(tweet) => Some(TweetData::new(&user, tweet)),
         None => {
             match server::get_tweet_by_id(tweet_id){
-                Some(tweet)=>{
-                    data::write::tweet(db, &tweet).await;
+                Ok(Some(tweet))=>{
+                    archive_tweets(db, &vec![tweet.clone()]).await;
                     Some(TweetData::new(&user, tweet))
                 },
-                None => {
+                Ok(None) => {
                     println!("Error, very likely this tweet was deleted");
                     None
                 }
+                Err(e) => {
+                    println!("Failed to load tweet {} because {}", tweet_id, e);
+                    None
+                }
             }
         }
     }
 }
 
-pub async fn has_new_tweets(db: &DatabaseConnection, twitter_handle: &str) -> bool {
+pub async fn has_new_tweets(
+    db: &DatabaseConnection,
+    twitter_handle: &str,
+) -> Result<bool, server::ServerError> {
     let since = data::read::latest_tweet_from_user_by_twitter_handle(db, twitter_handle)
         .await
         .expect("failed to get latest tweet")
@@ -140,7 +387,10 @@ pub async fn has_new_tweets(db: &DatabaseConnection, twitter_handle: &str) -> bo
     server::find_out_if_user_has_tweeted_since(twitter_handle, &since)
 }
 
-pub async fn load_users_new_tweets(db: &DatabaseConnection, twitter_handle: &str) -> Vec<Tweet> {
+pub async fn load_users_new_tweets(
+    db: &DatabaseConnection,
+    twitter_handle: &str,
+) -> Result<Vec<Tweet>, server::ServerError> {
     let since = data::read::latest_tweet_from_user_by_twitter_handle(db, twitter_handle)
         .await
         .expect("failed to get latest tweet")
@@ -149,14 +399,125 @@ pub async fn load_users_new_tweets(db: &DatabaseConnection, twitter_handle: &str
     server::get_users_tweets_since(twitter_handle, &since)
 }
 
-pub async fn search_tweets_in_db(db: &DatabaseConnection, search_query: &str) -> Vec<TweetData> {
-    vec_tweet_data_from_vec_tweet(db, data::read::search_tweets_in_db(db, search_query).await).await
+pub async fn search_tweets_in_db(
+    db: &DatabaseConnection,
+    search_query: &SearchQuery,
+    options: SearchOptions,
+) -> Vec<SearchResult> {
+    let (tweets, snippets): (Vec<Tweet>, Vec<Option<String>>) =
+        data::read::search_tweets_in_db(db, search_query, options)
+            .await
+            .into_iter()
+            .unzip();
+    vec_tweet_data_from_vec_tweet(db, tweets)
+        .await
+        .into_iter()
+        .zip(snippets)
+        .map(|(tweet_data, snippet)| SearchResult {
+            tweet_data,
+            snippet,
+        })
+        .collect()
+}
+
+/// "Did you mean" alternatives to a search input, each respelling the words
+/// that aren't in the archive with one of the closest words that are.
+pub async fn search_suggestions(
+    db: &DatabaseConnection,
+    search_input: &str,
+    search_query: &SearchQuery,
+) -> Vec<String> {
+    const MAX_SUGGESTIONS: usize = 3;
+
+    let mut respellings: Vec<(String, Vec<String>)> = Vec::new();
+    for word in search_query.words() {
+        let similar_words = data::read::similar_words(db, &word).await;
+        match similar_words.first() {
+            Some((_, 0)) => (),
+            Some(_) => respellings.push((
+                word,
+                similar_words
+                    .into_iter()
+                    .take(MAX_SUGGESTIONS)
+                    .map(|(similar_word, _)| similar_word)
+                    .collect(),
+            )),
+            None => (),
+        }
+    }
+
+    let mut suggestions: Vec<String> = Vec::new();
+    for i in 0..MAX_SUGGESTIONS {
+        let corrections = respellings
+            .iter()
+            .map(|(word, similar_words)| {
+                let similar_word = similar_words.get(i).unwrap_or(&similar_words[0]);
+                (word.clone(), similar_word.clone())
+            })
+            .collect();
+        let suggestion = respell(search_input, &corrections);
+        if suggestion != search_input && !suggestions.contains(&suggestion) {
+            suggestions.push(suggestion);
+        }
+    }
+    suggestions
+}
+
+pub async fn load_tracked_accounts(db: &DatabaseConnection) -> Vec<TrackedAccountData> {
+    data::read::tracked_accounts(db).await
+}
+
+/// Starts following a user by their handle, fetching their profile when they
+/// aren't archived yet.
+pub async fn track_account(db: &DatabaseConnection, twitter_handle: &str) {
+    let user = match load_user_from_twitter_handle(db, twitter_handle).await {
+        Some(user) => user,
+        None => {
+            println!("Can't track @{}, they aren't archived or on the server", twitter_handle);
+            return;
+        }
+    };
+    data::write::tracked_account(
+        db,
+        user.id
+            .as_u64()
+            .try_into()
+            .expect("Failed to parse u64 into i64"),
+    )
+    .await;
+}
+
+pub async fn untrack_account(db: &DatabaseConnection, user_id: i64) {
+    data::write::untracked_account(db, user_id).await;
+}
+
+/// Opens a link from a tweet in the system's web browser. Only web links are
+/// opened, anything else in a tweet could point at a local file or program.
+pub fn open_url(url: &str) -> Result<(), String> {
+    let url = match reqwest::Url::parse(url) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+        _ => return Err(format!("Not opening {}, it isn't a web link", url)),
+    };
+    // the url goes to the opener as a single argument, never through a shell
+    // that would read `&` or `|` in it as commands
+    let opener = if cfg!(target_os = "windows") {
+        std::process::Command::new("rundll32")
+            .args(["url.dll,FileProtocolHandler", url.as_str()])
+            .spawn()
+    } else if cfg!(target_os = "macos") {
+        std::process::Command::new("open").arg(url.as_str()).spawn()
+    } else {
+        std::process::Command::new("xdg-open").arg(url.as_str()).spawn()
+    };
+    opener
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open {} because {}", url, e))
 }
 
 pub async fn seed_conversation_from_tweets(db: &DatabaseConnection, tweets: &Vec<TweetData>) {
     let conversations: Vec<Vec<Tweet>> = tweets
         .iter()
-        .map(|tweet_data| {
+        .filter_map(|tweet_data| {
             server::get_conversation_by_tweet_id(
                 tweet_data
                     .tweet
@@ -165,10 +526,11 @@ pub async fn seed_conversation_from_tweets(db: &DatabaseConnection, tweets: &Vec
                     .try_into()
                     .expect("Failed to parse i64 from u64"),
             )
+            .ok()
         })
         .collect();
     let future_conversations = join_all(conversations.into_iter().map(|conversation| async move {
-        data::write::tweets(db, &conversation).await;
+        archive_tweets(db, &conversation).await;
     }));
     future_conversations.await;
 }
@@ -1,4 +1,5 @@
 pub mod entities;
+pub mod migration;
 pub mod read;
 pub mod setup;
 pub mod write;
@@ -5,7 +5,10 @@ pub mod prelude;
 pub mod conversations;
 pub mod seaql_migrations;
 
+pub mod tracked_accounts;
 pub mod tweet_references;
 pub mod tweets;
 
+pub mod user_aliases;
+pub mod user_snapshots;
 pub mod users;
@@ -3,7 +3,10 @@
 pub use super::conversations::Entity as Conversations;
 pub use super::seaql_migrations::Entity as SeaqlMigrations;
 
+pub use super::tracked_accounts::Entity as TrackedAccounts;
 pub use super::tweet_references::Entity as TweetReferences;
 pub use super::tweets::Entity as Tweets;
 
+pub use super::user_aliases::Entity as UserAliases;
+pub use super::user_snapshots::Entity as UserSnapshots;
 pub use super::users::Entity as Users;
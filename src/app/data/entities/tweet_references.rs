@@ -9,6 +9,7 @@ use crate::utils::TweetReferenceData;
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub source_tweet_id: i64,
+    #[sea_orm(primary_key, auto_increment = false)]
     pub reference_type: String,
     pub referenced_tweet_id: i64,
 }
@@ -4,6 +4,8 @@ use chrono::{DateTime, FixedOffset};
 use sea_orm::entity::prelude::*;
 use time::OffsetDateTime;
 
+use crate::utils::from_ron_column;
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "tweets")]
 pub struct Model {
@@ -13,6 +15,23 @@ pub struct Model {
     pub author_id: i64,
     pub conversation_id: i64,
     pub created_at: DateTime<FixedOffset>,
+    pub attachments: Option<String>,
+    pub context_annotations: Option<String>,
+    pub entities: Option<String>,
+    pub geo: Option<String>,
+    pub in_reply_to_user_id: Option<i64>,
+    pub lang: Option<String>,
+    pub non_public_metrics: Option<String>,
+    pub organic_metrics: Option<String>,
+    pub possibly_sensitive: Option<bool>,
+    pub promoted_metrics: Option<String>,
+    pub public_metrics: Option<String>,
+    pub referenced_tweets: Option<String>,
+    pub reply_settings: Option<String>,
+    pub source: Option<String>,
+    pub withheld: Option<String>,
+    /// Whether Twitter found any links in the text, kept for `has:link`
+    pub has_links: bool,
 }
 
 impl Model {
@@ -30,21 +49,23 @@ impl Model {
                 OffsetDateTime::from_unix_timestamp(self.created_at.timestamp())
                     .expect("Failed time conversion"),
             ),
-            attachments: None,
-            context_annotations: None,
-            entities: None,
-            geo: None,
-            in_reply_to_user_id: None,
-            lang: None,
-            non_public_metrics: None,
-            organic_metrics: None,
-            possibly_sensitive: None,
-            promoted_metrics: None,
-            public_metrics: None,
-            referenced_tweets: None,
-            reply_settings: None,
-            source: None,
-            withheld: None,
+            attachments: from_ron_column(&self.attachments),
+            context_annotations: from_ron_column(&self.context_annotations),
+            entities: from_ron_column(&self.entities),
+            geo: from_ron_column(&self.geo),
+            in_reply_to_user_id: self
+                .in_reply_to_user_id
+                .map(|id| twitter_v2::id::NumericId::new(id.try_into().unwrap())),
+            lang: self.lang.clone(),
+            non_public_metrics: from_ron_column(&self.non_public_metrics),
+            organic_metrics: from_ron_column(&self.organic_metrics),
+            possibly_sensitive: self.possibly_sensitive,
+            promoted_metrics: from_ron_column(&self.promoted_metrics),
+            public_metrics: from_ron_column(&self.public_metrics),
+            referenced_tweets: from_ron_column(&self.referenced_tweets),
+            reply_settings: from_ron_column(&self.reply_settings),
+            source: self.source.clone(),
+            withheld: from_ron_column(&self.withheld),
         }
     }
 }
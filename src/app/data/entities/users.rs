@@ -1,6 +1,10 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.8.0
 
+use chrono::{DateTime, FixedOffset};
 use sea_orm::entity::prelude::*;
+use time::OffsetDateTime;
+
+use crate::utils::from_ron_column;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "users")]
@@ -9,7 +13,17 @@ pub struct Model {
     pub id: i64,
     pub name: String,
     pub username: String,
-    pub description: String,
+    pub description: Option<String>,
+    pub created_at: Option<DateTime<FixedOffset>>,
+    pub entities: Option<String>,
+    pub location: Option<String>,
+    pub pinned_tweet_id: Option<i64>,
+    pub profile_image_url: Option<String>,
+    pub protected: Option<bool>,
+    pub public_metrics: Option<String>,
+    pub url: Option<String>,
+    pub verified: Option<bool>,
+    pub withheld: Option<String>,
 }
 
 impl Model {
@@ -18,17 +32,25 @@ impl Model {
             id: twitter_v2::id::NumericId::new(self.id.try_into().unwrap()),
             name: self.name.clone(),
             username: self.username.clone(),
-            created_at: None,
-            description: Some(self.description.clone()),
-            entities: None,
-            location: None,
-            pinned_tweet_id: None,
-            profile_image_url: None,
-            protected: None,
-            public_metrics: None,
-            url: None,
-            verified: None,
-            withheld: None,
+            created_at: self.created_at.map(|created_at| {
+                OffsetDateTime::from_unix_timestamp(created_at.timestamp())
+                    .expect("Failed time conversion")
+            }),
+            description: self.description.clone(),
+            entities: from_ron_column(&self.entities),
+            location: self.location.clone(),
+            pinned_tweet_id: self
+                .pinned_tweet_id
+                .map(|id| twitter_v2::id::NumericId::new(id.try_into().unwrap())),
+            profile_image_url: self
+                .profile_image_url
+                .as_ref()
+                .and_then(|url| url.parse().ok()),
+            protected: self.protected,
+            public_metrics: from_ron_column(&self.public_metrics),
+            url: self.url.as_ref().and_then(|url| url.parse().ok()),
+            verified: self.verified,
+            withheld: from_ron_column(&self.withheld),
         }
     }
 }
@@ -37,6 +59,18 @@ impl Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::tweets::Entity")]
     Tweets,
+    #[sea_orm(has_many = "super::user_snapshots::Entity")]
+    UserSnapshots,
+    #[sea_orm(has_many = "super::user_aliases::Entity")]
+    UserAliases,
+    #[sea_orm(has_one = "super::tracked_accounts::Entity")]
+    TrackedAccounts,
+}
+
+impl Related<super::tracked_accounts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TrackedAccounts.def()
+    }
 }
 
 impl Related<super::tweets::Entity> for Entity {
@@ -45,4 +79,16 @@ impl Related<super::tweets::Entity> for Entity {
     }
 }
 
+impl Related<super::user_aliases::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserAliases.def()
+    }
+}
+
+impl Related<super::user_snapshots::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserSnapshots.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,153 @@
+use sea_orm::{ConnectionTrait, Statement};
+use sea_orm_migration::prelude::*;
+
+mod m20220822_000001_create_tables;
+mod m20220822_000002_add_tweet_payload_columns;
+mod m20220822_000003_add_user_profile_columns;
+mod m20220822_000004_create_user_snapshots;
+mod m20220822_000005_create_user_aliases;
+mod m20220822_000006_create_tweets_fts;
+mod m20220822_000007_add_author_to_tweets_fts;
+mod m20220822_000008_create_tweet_words;
+mod m20220822_000009_key_tweet_references_by_type;
+mod m20220822_000010_create_tracked_accounts;
+mod m20220822_000011_make_user_description_nullable;
+mod m20220822_000012_add_has_links_to_tweets;
+
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20220822_000001_create_tables::Migration),
+            Box::new(m20220822_000002_add_tweet_payload_columns::Migration),
+            Box::new(m20220822_000003_add_user_profile_columns::Migration),
+            Box::new(m20220822_000004_create_user_snapshots::Migration),
+            Box::new(m20220822_000005_create_user_aliases::Migration),
+            Box::new(m20220822_000006_create_tweets_fts::Migration),
+            Box::new(m20220822_000007_add_author_to_tweets_fts::Migration),
+            Box::new(m20220822_000008_create_tweet_words::Migration),
+            Box::new(m20220822_000009_key_tweet_references_by_type::Migration),
+            Box::new(m20220822_000010_create_tracked_accounts::Migration),
+            Box::new(m20220822_000011_make_user_description_nullable::Migration),
+            Box::new(m20220822_000012_add_has_links_to_tweets::Migration),
+        ]
+    }
+}
+
+/// Runs a raw SQL statement, for what the schema builder can't express.
+pub(crate) async fn execute(manager: &SchemaManager, statement: &str) -> Result<(), DbErr> {
+    manager
+        .get_connection()
+        .execute(Statement::from_string(
+            manager.get_database_backend(),
+            statement.to_string(),
+        ))
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn execute_all(manager: &SchemaManager, statements: &[&str]) -> Result<(), DbErr> {
+    for statement in statements {
+        execute(manager, statement).await?;
+    }
+    Ok(())
+}
+
+#[derive(Iden)]
+pub enum Users {
+    Table,
+    Id,
+    Name,
+    Username,
+    Description,
+    CreatedAt,
+    Entities,
+    Location,
+    PinnedTweetId,
+    ProfileImageUrl,
+    Protected,
+    PublicMetrics,
+    Url,
+    Verified,
+    Withheld,
+}
+
+#[derive(Iden)]
+pub enum Conversations {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+pub enum Tweets {
+    Table,
+    Id,
+    Content,
+    AuthorId,
+    ConversationId,
+    CreatedAt,
+    Attachments,
+    ContextAnnotations,
+    Entities,
+    Geo,
+    InReplyToUserId,
+    Lang,
+    NonPublicMetrics,
+    OrganicMetrics,
+    PossiblySensitive,
+    PromotedMetrics,
+    PublicMetrics,
+    ReferencedTweets,
+    ReplySettings,
+    Source,
+    Withheld,
+    HasLinks,
+}
+
+#[derive(Iden)]
+pub enum TweetReferences {
+    Table,
+    SourceTweetId,
+    ReferenceType,
+    ReferencedTweetId,
+}
+
+#[derive(Iden)]
+pub enum UserSnapshots {
+    Table,
+    Id,
+    UserId,
+    FetchedAt,
+    Name,
+    Username,
+    Description,
+    Location,
+    Url,
+    ProfileImageUrl,
+    FollowersCount,
+    FollowingCount,
+    TweetCount,
+    ListedCount,
+    Verified,
+    Protected,
+}
+
+#[derive(Iden)]
+pub enum UserAliases {
+    Table,
+    Handle,
+    UserId,
+    Username,
+    FirstSeenAt,
+    LastSeenAt,
+}
+
+#[derive(Iden)]
+pub enum TrackedAccounts {
+    Table,
+    UserId,
+    AddedAt,
+    LastSyncedAt,
+}
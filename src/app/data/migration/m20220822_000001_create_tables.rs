@@ -0,0 +1,197 @@
+use sea_orm_migration::prelude::*;
+
+use super::{Conversations, TweetReferences, Tweets, Users};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220822_000001_create_tables"
+    }
+}
+
+// Every statement uses `if_not_exists` so archives created by hand before
+// migrations existed are adopted rather than rejected.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Users::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Users::Id)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Users::Name).string().not_null())
+                    .col(ColumnDef::new(Users::Username).string().not_null())
+                    .col(ColumnDef::new(Users::Description).text().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Conversations::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Conversations::Id)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Tweets::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Tweets::Id)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Tweets::Content).text().not_null())
+                    .col(ColumnDef::new(Tweets::AuthorId).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(Tweets::ConversationId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Tweets::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-tweets-author_id")
+                            .from(Tweets::Table, Tweets::AuthorId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-tweets-conversation_id")
+                            .from(Tweets::Table, Tweets::ConversationId)
+                            .to(Conversations::Table, Conversations::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(TweetReferences::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TweetReferences::SourceTweetId)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TweetReferences::ReferenceType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TweetReferences::ReferencedTweetId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-tweet_references-source_tweet_id")
+                            .from(TweetReferences::Table, TweetReferences::SourceTweetId)
+                            .to(Tweets::Table, Tweets::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-tweet_references-referenced_tweet_id")
+                            .from(TweetReferences::Table, TweetReferences::ReferencedTweetId)
+                            .to(Tweets::Table, Tweets::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx-users-username")
+                    .table(Users::Table)
+                    .col(Users::Username)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx-tweets-author_id")
+                    .table(Tweets::Table)
+                    .col(Tweets::AuthorId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx-tweets-conversation_id")
+                    .table(Tweets::Table)
+                    .col(Tweets::ConversationId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx-tweets-created_at")
+                    .table(Tweets::Table)
+                    .col(Tweets::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx-tweet_references-referenced_tweet_id")
+                    .table(TweetReferences::Table)
+                    .col(TweetReferences::ReferencedTweetId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TweetReferences::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Tweets::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Conversations::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Users::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,87 @@
+use sea_orm_migration::prelude::*;
+
+use super::Tweets;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220822_000002_add_tweet_payload_columns"
+    }
+}
+
+// Nested parts of the payload are stored as RON text, the same format the
+// server delivers them in.
+fn payload_columns() -> Vec<ColumnDef> {
+    vec![
+        ColumnDef::new(Tweets::Attachments).text().null().to_owned(),
+        ColumnDef::new(Tweets::ContextAnnotations).text().null().to_owned(),
+        ColumnDef::new(Tweets::Entities).text().null().to_owned(),
+        ColumnDef::new(Tweets::Geo).text().null().to_owned(),
+        ColumnDef::new(Tweets::InReplyToUserId)
+            .big_integer()
+            .null()
+            .to_owned(),
+        ColumnDef::new(Tweets::Lang).string().null().to_owned(),
+        ColumnDef::new(Tweets::NonPublicMetrics).text().null().to_owned(),
+        ColumnDef::new(Tweets::OrganicMetrics).text().null().to_owned(),
+        ColumnDef::new(Tweets::PossiblySensitive)
+            .boolean()
+            .null()
+            .to_owned(),
+        ColumnDef::new(Tweets::PromotedMetrics).text().null().to_owned(),
+        ColumnDef::new(Tweets::PublicMetrics).text().null().to_owned(),
+        ColumnDef::new(Tweets::ReferencedTweets).text().null().to_owned(),
+        ColumnDef::new(Tweets::ReplySettings).string().null().to_owned(),
+        ColumnDef::new(Tweets::Source).string().null().to_owned(),
+        ColumnDef::new(Tweets::Withheld).text().null().to_owned(),
+    ]
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    // SQLite only accepts one column per ALTER TABLE statement
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for mut column in payload_columns() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Tweets::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Tweets::Attachments,
+            Tweets::ContextAnnotations,
+            Tweets::Entities,
+            Tweets::Geo,
+            Tweets::InReplyToUserId,
+            Tweets::Lang,
+            Tweets::NonPublicMetrics,
+            Tweets::OrganicMetrics,
+            Tweets::PossiblySensitive,
+            Tweets::PromotedMetrics,
+            Tweets::PublicMetrics,
+            Tweets::ReferencedTweets,
+            Tweets::ReplySettings,
+            Tweets::Source,
+            Tweets::Withheld,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Tweets::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use sea_orm_migration::prelude::*;
+
+use super::Users;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220822_000003_add_user_profile_columns"
+    }
+}
+
+fn profile_columns() -> Vec<ColumnDef> {
+    vec![
+        ColumnDef::new(Users::CreatedAt)
+            .timestamp_with_time_zone()
+            .null()
+            .to_owned(),
+        ColumnDef::new(Users::Entities).text().null().to_owned(),
+        ColumnDef::new(Users::Location).string().null().to_owned(),
+        ColumnDef::new(Users::PinnedTweetId)
+            .big_integer()
+            .null()
+            .to_owned(),
+        ColumnDef::new(Users::ProfileImageUrl).text().null().to_owned(),
+        ColumnDef::new(Users::Protected).boolean().null().to_owned(),
+        ColumnDef::new(Users::PublicMetrics).text().null().to_owned(),
+        ColumnDef::new(Users::Url).text().null().to_owned(),
+        ColumnDef::new(Users::Verified).boolean().null().to_owned(),
+        ColumnDef::new(Users::Withheld).text().null().to_owned(),
+    ]
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for mut column in profile_columns() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Users::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Users::CreatedAt,
+            Users::Entities,
+            Users::Location,
+            Users::PinnedTweetId,
+            Users::ProfileImageUrl,
+            Users::Protected,
+            Users::PublicMetrics,
+            Users::Url,
+            Users::Verified,
+            Users::Withheld,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Users::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
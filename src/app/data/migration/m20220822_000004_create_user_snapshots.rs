@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+use super::{UserSnapshots, Users};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220822_000004_create_user_snapshots"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserSnapshots::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserSnapshots::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(UserSnapshots::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserSnapshots::FetchedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(UserSnapshots::Name).string().not_null())
+                    .col(ColumnDef::new(UserSnapshots::Username).string().not_null())
+                    .col(ColumnDef::new(UserSnapshots::Description).text().null())
+                    .col(ColumnDef::new(UserSnapshots::Location).string().null())
+                    .col(ColumnDef::new(UserSnapshots::Url).text().null())
+                    .col(ColumnDef::new(UserSnapshots::ProfileImageUrl).text().null())
+                    .col(
+                        ColumnDef::new(UserSnapshots::FollowersCount)
+                            .big_integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserSnapshots::FollowingCount)
+                            .big_integer()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(UserSnapshots::TweetCount).big_integer().null())
+                    .col(ColumnDef::new(UserSnapshots::ListedCount).big_integer().null())
+                    .col(ColumnDef::new(UserSnapshots::Verified).boolean().null())
+                    .col(ColumnDef::new(UserSnapshots::Protected).boolean().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-user_snapshots-user_id")
+                            .from(UserSnapshots::Table, UserSnapshots::UserId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx-user_snapshots-user_id-fetched_at")
+                    .table(UserSnapshots::Table)
+                    .col(UserSnapshots::UserId)
+                    .col(UserSnapshots::FetchedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserSnapshots::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,79 @@
+use sea_orm::{ConnectionTrait, Statement};
+use sea_orm_migration::prelude::*;
+
+use super::{UserAliases, Users};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220822_000005_create_user_aliases"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserAliases::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(UserAliases::Handle).string().not_null())
+                    .col(ColumnDef::new(UserAliases::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(UserAliases::Username).string().not_null())
+                    .col(
+                        ColumnDef::new(UserAliases::FirstSeenAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserAliases::LastSeenAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(UserAliases::Handle)
+                            .col(UserAliases::UserId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-user_aliases-user_id")
+                            .from(UserAliases::Table, UserAliases::UserId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx-user_aliases-user_id")
+                    .table(UserAliases::Table)
+                    .col(UserAliases::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // every archived user is at least known by their current handle
+        let db = manager.get_connection();
+        db.execute(Statement::from_string(
+            manager.get_database_backend(),
+            "INSERT INTO user_aliases (handle, user_id, username, first_seen_at, last_seen_at) \
+             SELECT LOWER(username), id, username, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP FROM users"
+                .to_string(),
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserAliases::Table).to_owned())
+            .await
+    }
+}
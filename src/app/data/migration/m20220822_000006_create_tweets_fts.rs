@@ -0,0 +1,60 @@
+use sea_orm::DbBackend;
+use sea_orm_migration::prelude::*;
+
+use super::execute_all;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220822_000006_create_tweets_fts"
+    }
+}
+
+// An external content FTS5 index over `tweets.content`. The triggers keep it in
+// step with every insert, upsert and delete, and `rebuild` backfills the
+// tweets archived before the index existed.
+const UP: [&str; 5] = [
+    "CREATE VIRTUAL TABLE IF NOT EXISTS tweets_fts USING fts5(
+        content,
+        content = 'tweets',
+        content_rowid = 'id',
+        tokenize = 'porter unicode61'
+    )",
+    "CREATE TRIGGER IF NOT EXISTS tweets_fts_after_insert AFTER INSERT ON tweets BEGIN
+        INSERT INTO tweets_fts(rowid, content) VALUES (new.id, new.content);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS tweets_fts_after_delete AFTER DELETE ON tweets BEGIN
+        INSERT INTO tweets_fts(tweets_fts, rowid, content) VALUES ('delete', old.id, old.content);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS tweets_fts_after_update AFTER UPDATE OF content ON tweets BEGIN
+        INSERT INTO tweets_fts(tweets_fts, rowid, content) VALUES ('delete', old.id, old.content);
+        INSERT INTO tweets_fts(rowid, content) VALUES (new.id, new.content);
+    END",
+    "INSERT INTO tweets_fts(tweets_fts) VALUES ('rebuild')",
+];
+
+const DOWN: [&str; 4] = [
+    "DROP TRIGGER IF EXISTS tweets_fts_after_update",
+    "DROP TRIGGER IF EXISTS tweets_fts_after_delete",
+    "DROP TRIGGER IF EXISTS tweets_fts_after_insert",
+    "DROP TABLE IF EXISTS tweets_fts",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    // FTS5 is SQLite only, other backends keep searching with LIKE
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => execute_all(manager, &UP).await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => execute_all(manager, &DOWN).await,
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use sea_orm::DbBackend;
+use sea_orm_migration::prelude::*;
+
+use super::execute_all;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220822_000007_add_author_to_tweets_fts"
+    }
+}
+
+const DROP_CONTENT_ONLY_INDEX: [&str; 4] = [
+    "DROP TRIGGER IF EXISTS tweets_fts_after_update",
+    "DROP TRIGGER IF EXISTS tweets_fts_after_delete",
+    "DROP TRIGGER IF EXISTS tweets_fts_after_insert",
+    "DROP TABLE IF EXISTS tweets_fts",
+];
+
+// The author's name and handle live in `users`, so the index can no longer read
+// its text from `tweets` and keeps its own copy instead. The author column lets
+// ranking weigh a match in the tweet text above a match on who wrote it.
+const UP: [&str; 7] = [
+    "CREATE VIRTUAL TABLE IF NOT EXISTS tweets_fts USING fts5(
+        content,
+        author,
+        tokenize = 'porter unicode61'
+    )",
+    "CREATE TRIGGER IF NOT EXISTS tweets_fts_after_insert AFTER INSERT ON tweets BEGIN
+        INSERT INTO tweets_fts(rowid, content, author)
+        SELECT new.id, new.content, COALESCE((SELECT name || ' ' || username FROM users WHERE id = new.author_id), '');
+    END",
+    "CREATE TRIGGER IF NOT EXISTS tweets_fts_after_delete AFTER DELETE ON tweets BEGIN
+        DELETE FROM tweets_fts WHERE rowid = old.id;
+    END",
+    "CREATE TRIGGER IF NOT EXISTS tweets_fts_after_update AFTER UPDATE OF content, author_id ON tweets BEGIN
+        DELETE FROM tweets_fts WHERE rowid = old.id;
+        INSERT INTO tweets_fts(rowid, content, author)
+        SELECT new.id, new.content, COALESCE((SELECT name || ' ' || username FROM users WHERE id = new.author_id), '');
+    END",
+    "CREATE TRIGGER IF NOT EXISTS tweets_fts_after_user_update AFTER UPDATE OF name, username ON users BEGIN
+        UPDATE tweets_fts SET author = new.name || ' ' || new.username
+        WHERE rowid IN (SELECT id FROM tweets WHERE author_id = new.id);
+    END",
+    "DELETE FROM tweets_fts",
+    "INSERT INTO tweets_fts(rowid, content, author)
+     SELECT tweets.id, tweets.content, COALESCE(users.name || ' ' || users.username, '')
+     FROM tweets LEFT JOIN users ON users.id = tweets.author_id",
+];
+
+const DOWN: [&str; 5] = [
+    "DROP TRIGGER IF EXISTS tweets_fts_after_user_update",
+    "DROP TRIGGER IF EXISTS tweets_fts_after_update",
+    "DROP TRIGGER IF EXISTS tweets_fts_after_delete",
+    "DROP TRIGGER IF EXISTS tweets_fts_after_insert",
+    "DROP TABLE IF EXISTS tweets_fts",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => {
+                execute_all(manager, &DROP_CONTENT_ONLY_INDEX).await?;
+                execute_all(manager, &UP).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // restores the content only index from the previous migration
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => {
+                execute_all(manager, &DOWN).await?;
+                super::m20220822_000006_create_tweets_fts::Migration
+                    .up(manager)
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+}
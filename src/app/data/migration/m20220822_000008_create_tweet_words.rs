@@ -0,0 +1,62 @@
+use sea_orm::DbBackend;
+use sea_orm_migration::prelude::*;
+
+use super::execute_all;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220822_000008_create_tweet_words"
+    }
+}
+
+// `tweets_fts` stems its terms, so "econometrics" is stored as "econometr" and
+// can't be offered back as a spelling. This second index keeps every word of
+// `tweets.content` as written, and `tweet_words` exposes it as a vocabulary
+// with the number of tweets using each word.
+const UP: [&str; 6] = [
+    "CREATE VIRTUAL TABLE IF NOT EXISTS tweet_words_fts USING fts5(
+        content,
+        content = 'tweets',
+        content_rowid = 'id',
+        tokenize = 'unicode61'
+    )",
+    "CREATE VIRTUAL TABLE IF NOT EXISTS tweet_words USING fts5vocab(tweet_words_fts, 'row')",
+    "CREATE TRIGGER IF NOT EXISTS tweet_words_fts_after_insert AFTER INSERT ON tweets BEGIN
+        INSERT INTO tweet_words_fts(rowid, content) VALUES (new.id, new.content);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS tweet_words_fts_after_delete AFTER DELETE ON tweets BEGIN
+        INSERT INTO tweet_words_fts(tweet_words_fts, rowid, content) VALUES ('delete', old.id, old.content);
+    END",
+    "CREATE TRIGGER IF NOT EXISTS tweet_words_fts_after_update AFTER UPDATE OF content ON tweets BEGIN
+        INSERT INTO tweet_words_fts(tweet_words_fts, rowid, content) VALUES ('delete', old.id, old.content);
+        INSERT INTO tweet_words_fts(rowid, content) VALUES (new.id, new.content);
+    END",
+    "INSERT INTO tweet_words_fts(tweet_words_fts) VALUES ('rebuild')",
+];
+
+const DOWN: [&str; 5] = [
+    "DROP TRIGGER IF EXISTS tweet_words_fts_after_update",
+    "DROP TRIGGER IF EXISTS tweet_words_fts_after_delete",
+    "DROP TRIGGER IF EXISTS tweet_words_fts_after_insert",
+    "DROP TABLE IF EXISTS tweet_words",
+    "DROP TABLE IF EXISTS tweet_words_fts",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => execute_all(manager, &UP).await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DbBackend::Sqlite => execute_all(manager, &DOWN).await,
+            _ => Ok(()),
+        }
+    }
+}
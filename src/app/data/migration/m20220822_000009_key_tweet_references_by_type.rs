@@ -0,0 +1,144 @@
+use sea_orm::{ConnectionTrait, Statement};
+use sea_orm_migration::prelude::*;
+
+use super::{TweetReferences, Tweets};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220822_000009_key_tweet_references_by_type"
+    }
+}
+
+// A reply that also quotes another tweet holds two references, which the
+// original single column primary key on `source_tweet_id` couldn't store.
+// SQLite can't change a primary key in place, so the table is rebuilt under a
+// temporary name and swapped in. The referenced tweet also loses its foreign
+// key, as references now point at tweets that were deleted or lie beyond how
+// far referenced tweets are fetched.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let rebuilt_table = Alias::new("tweet_references_rebuilt");
+        manager
+            .create_table(
+                Table::create()
+                    .table(rebuilt_table.clone())
+                    .col(
+                        ColumnDef::new(TweetReferences::SourceTweetId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TweetReferences::ReferenceType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TweetReferences::ReferencedTweetId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(TweetReferences::SourceTweetId)
+                            .col(TweetReferences::ReferenceType),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-tweet_references-source")
+                            .from(rebuilt_table.clone(), TweetReferences::SourceTweetId)
+                            .to(Tweets::Table, Tweets::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                "INSERT INTO tweet_references_rebuilt (source_tweet_id, reference_type, referenced_tweet_id) \
+                 SELECT source_tweet_id, reference_type, referenced_tweet_id FROM tweet_references"
+                    .to_owned(),
+            ))
+            .await?;
+
+        swap_in(manager, rebuilt_table).await
+    }
+
+    // keeps a single reference for each tweet, which is all the old key allows
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let rebuilt_table = Alias::new("tweet_references_rebuilt");
+        manager
+            .create_table(
+                Table::create()
+                    .table(rebuilt_table.clone())
+                    .col(
+                        ColumnDef::new(TweetReferences::SourceTweetId)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TweetReferences::ReferenceType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TweetReferences::ReferencedTweetId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-tweet_references-source_tweet_id")
+                            .from(rebuilt_table.clone(), TweetReferences::SourceTweetId)
+                            .to(Tweets::Table, Tweets::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                "INSERT INTO tweet_references_rebuilt (source_tweet_id, reference_type, referenced_tweet_id) \
+                 SELECT source_tweet_id, reference_type, referenced_tweet_id FROM tweet_references AS reference \
+                 WHERE reference_type = (SELECT MIN(reference_type) FROM tweet_references \
+                 WHERE source_tweet_id = reference.source_tweet_id)"
+                    .to_owned(),
+            ))
+            .await?;
+
+        swap_in(manager, rebuilt_table).await
+    }
+}
+
+/// Replaces `tweet_references` with the rebuilt table and restores its index.
+async fn swap_in(manager: &SchemaManager, rebuilt_table: Alias) -> Result<(), DbErr> {
+    manager
+        .drop_table(Table::drop().table(TweetReferences::Table).to_owned())
+        .await?;
+
+    manager
+        .rename_table(
+            Table::rename()
+                .table(rebuilt_table, TweetReferences::Table)
+                .to_owned(),
+        )
+        .await?;
+
+    manager
+        .create_index(
+            Index::create()
+                .if_not_exists()
+                .name("idx-tweet_references-referenced_tweet_id")
+                .table(TweetReferences::Table)
+                .col(TweetReferences::ReferencedTweetId)
+                .to_owned(),
+        )
+        .await
+}
@@ -0,0 +1,69 @@
+use sea_orm::{ConnectionTrait, Statement};
+use sea_orm_migration::prelude::*;
+
+use super::{TrackedAccounts, Users};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220822_000010_create_tracked_accounts"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TrackedAccounts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TrackedAccounts::UserId)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TrackedAccounts::AddedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TrackedAccounts::LastSyncedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-tracked_accounts-user_id")
+                            .from(TrackedAccounts::Table, TrackedAccounts::UserId)
+                            .to(Users::Table, Users::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Archives made before accounts could be chosen followed a single
+        // account, which is the author with the most archived tweets.
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                manager.get_database_backend(),
+                "INSERT INTO tracked_accounts (user_id, added_at, last_synced_at) \
+                 SELECT author_id, CURRENT_TIMESTAMP, NULL FROM tweets \
+                 GROUP BY author_id ORDER BY COUNT(*) DESC LIMIT 1"
+                    .to_owned(),
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TrackedAccounts::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,62 @@
+use sea_orm::DbBackend;
+use sea_orm_migration::prelude::*;
+
+use super::{execute, execute_all, Users};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220822_000011_make_user_description_nullable"
+    }
+}
+
+// SQLite can't change a column's constraints in place, so the column is
+// renamed out of the way, recreated and copied back. Users without a bio were
+// stored with an empty one, which becomes NULL.
+const SQLITE_UP: [&str; 4] = [
+    "ALTER TABLE users RENAME COLUMN description TO description_not_null",
+    "ALTER TABLE users ADD COLUMN description text NULL",
+    "UPDATE users SET description = NULLIF(description_not_null, '')",
+    "ALTER TABLE users DROP COLUMN description_not_null",
+];
+
+const SQLITE_DOWN: [&str; 4] = [
+    "ALTER TABLE users RENAME COLUMN description TO description_null",
+    "ALTER TABLE users ADD COLUMN description text NOT NULL DEFAULT ''",
+    "UPDATE users SET description = COALESCE(description_null, '')",
+    "ALTER TABLE users DROP COLUMN description_null",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::Sqlite {
+            return execute_all(manager, &SQLITE_UP).await;
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .modify_column(ColumnDef::new(Users::Description).text().null())
+                    .to_owned(),
+            )
+            .await?;
+        execute(manager, "UPDATE users SET description = NULL WHERE description = ''").await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DbBackend::Sqlite {
+            return execute_all(manager, &SQLITE_DOWN).await;
+        }
+        execute(manager, "UPDATE users SET description = '' WHERE description IS NULL").await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .modify_column(ColumnDef::new(Users::Description).text().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+}
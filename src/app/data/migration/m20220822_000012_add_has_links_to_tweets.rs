@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+
+use super::{execute, Tweets};
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220822_000012_add_has_links_to_tweets"
+    }
+}
+
+// Tweets written from here on get the flag from their url entities as they're
+// written. Those already archived are flagged from their entities as stored
+// so far, where a non-empty url list reads `urls:Some([(`.
+const BACKFILL: &str = "UPDATE tweets SET has_links = TRUE WHERE entities LIKE '%urls:Some([(%'";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tweets::Table)
+                    .add_column(
+                        ColumnDef::new(Tweets::HasLinks)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        execute(manager, BACKFILL).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tweets::Table)
+                    .drop_column(Tweets::HasLinks)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -1,13 +1,30 @@
-use crate::{app::load_user_from_twitter_handle, utils::TweetReferenceData};
+use crate::{
+    app::load_user_from_twitter_handle,
+    search::{edit_distance, typo_allowance, Filter, SearchOptions, SearchOrder, SearchQuery, Term},
+    utils::{
+        TrackedAccountData, TweetReferenceData, UserSnapshotData, SNIPPET_HIGHLIGHT_END,
+        SNIPPET_HIGHLIGHT_START,
+    },
+};
 
 use super::entities::prelude::*;
 use super::entities::*;
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use sea_orm::sea_query::{Expr, SimpleExpr};
 use sea_orm::{
-    ColumnTrait, DatabaseConnection, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect,
-    RelationTrait,
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait,
+    FromQueryResult, Order, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Statement, Value,
 };
+use std::collections::HashMap;
+use twitter_v2::data::ReferencedTweetKind;
 use twitter_v2::{Tweet, User};
 
+const SEARCH_RESULT_LIMIT: u64 = 500;
+// bm25 weights for the tweets_fts columns, a match in the tweet text counts
+// for more than a match on the author's name or handle
+const CONTENT_RANK_WEIGHT: f64 = 10.0;
+const AUTHOR_RANK_WEIGHT: f64 = 2.0;
+
 pub async fn tweet_by_id(db: &DatabaseConnection, id: i64) -> Option<Tweet> {
     let db = db as &DatabaseConnection;
 
@@ -22,18 +39,88 @@ pub async fn tweet_by_id(db: &DatabaseConnection, id: i64) -> Option<Tweet> {
     }
 }
 
-pub async fn tweet_reference_by_id(db: &DatabaseConnection, id: i64) -> Option<TweetReferenceData> {
-    let db = db as &DatabaseConnection;
+/// Every reference the tweet holds, as it can both reply to one tweet and
+/// quote another.
+pub async fn tweet_references_by_id(db: &DatabaseConnection, id: i64) -> Vec<TweetReferenceData> {
+    TweetReferences::find()
+        .filter(tweet_references::Column::SourceTweetId.eq(id))
+        .all(db)
+        .await
+        .expect("Failed to get tweet references")
+        .into_iter()
+        .map(|b| b.to_tweet_reference_data())
+        .collect()
+}
 
-    let tweet_reference = TweetReferences::find_by_id(id)
-        .one(db)
+/// The archived tweets that reference `tweet_id` in the given way, such as its
+/// replies or quote tweets, oldest first.
+pub async fn referencing_tweets(
+    db: &DatabaseConnection,
+    tweet_id: i64,
+    reference_type: &ReferencedTweetKind,
+) -> Vec<Tweet> {
+    let source_tweet_ids: Vec<i64> = TweetReferences::find()
+        .filter(tweet_references::Column::ReferencedTweetId.eq(tweet_id))
+        .filter(
+            tweet_references::Column::ReferenceType
+                .eq(TweetReferenceData::kind_to_string(reference_type)),
+        )
+        .all(db)
         .await
-        .expect("Failed to open the result option model tweet");
+        .expect("Failed to get tweet references")
+        .into_iter()
+        .map(|b| b.source_tweet_id)
+        .collect();
 
-    match tweet_reference {
-        Some(tweet_reference) => Some(tweet_reference.to_tweet_reference_data()),
-        None => None,
+    Tweets::find()
+        .filter(tweets::Column::Id.is_in(source_tweet_ids))
+        .order_by_asc(tweets::Column::CreatedAt)
+        .all(db)
+        .await
+        .expect("Failed to get referencing tweets")
+        .into_iter()
+        .map(|b| b.to_tweet())
+        .collect()
+}
+
+pub async fn replies_to_tweet(db: &DatabaseConnection, tweet_id: i64) -> Vec<Tweet> {
+    referencing_tweets(db, tweet_id, &ReferencedTweetKind::RepliedTo).await
+}
+
+pub async fn quotes_of_tweet(db: &DatabaseConnection, tweet_id: i64) -> Vec<Tweet> {
+    referencing_tweets(db, tweet_id, &ReferencedTweetKind::Quoted).await
+}
+
+pub async fn retweets_of_tweet(db: &DatabaseConnection, tweet_id: i64) -> Vec<Tweet> {
+    referencing_tweets(db, tweet_id, &ReferencedTweetKind::Retweeted).await
+}
+
+/// The archived tweets `tweet_id` replies to, quotes or retweets. Tweets synced
+/// before their references were recorded still list them in their own
+/// `referenced_tweets` column, which is used when the table has none.
+pub async fn referenced_tweets(db: &DatabaseConnection, tweet_id: i64) -> Vec<(ReferencedTweetKind, Tweet)> {
+    let mut references = tweet_references_by_id(db, tweet_id).await;
+
+    if references.is_empty() {
+        if let Some(tweet) = tweet_by_id(db, tweet_id).await {
+            references = tweet
+                .referenced_tweets
+                .unwrap_or_default()
+                .iter()
+                .map(|referenced_tweet| {
+                    TweetReferenceData::from_referenced_tweet(tweet_id, referenced_tweet)
+                })
+                .collect();
+        }
     }
+
+    let mut referenced_tweets = Vec::new();
+    for reference in references {
+        if let Some(tweet) = tweet_by_id(db, reference.reference_tweet_id).await {
+            referenced_tweets.push((reference.reference_type, tweet));
+        }
+    }
+    referenced_tweets
 }
 
 pub async fn user_by_id(db: &DatabaseConnection, id: i64) -> Option<User> {
@@ -50,21 +137,95 @@ pub async fn user_by_id(db: &DatabaseConnection, id: i64) -> Option<User> {
     }
 }
 
-pub async fn user_by_twitter_handle(db: &DatabaseConnection, twitter_handle: &str) -> Option<User> {
-    let db = db as &DatabaseConnection;
+/// Resolves a handle, current or former and in any letter case, to the id of
+/// the user who most recently went by it.
+pub async fn user_id_by_twitter_handle(db: &DatabaseConnection, twitter_handle: &str) -> Option<i64> {
+    let handle = twitter_handle.trim_start_matches('@').to_lowercase();
 
-    let user = Users::find()
-        .filter(users::Column::Username.eq(twitter_handle))
+    let alias = UserAliases::find()
+        .filter(user_aliases::Column::Handle.eq(handle))
+        .order_by_desc(user_aliases::Column::LastSeenAt)
         .one(db)
         .await
-        .expect("Failed to open the result option model tweet");
+        .expect("Failed to resolve twitter handle");
 
-    match user {
-        Some(user) => Some(user.to_twitter_user()),
+    match alias {
+        Some(alias) => Some(alias.user_id),
         None => None,
     }
 }
 
+pub async fn user_by_twitter_handle(db: &DatabaseConnection, twitter_handle: &str) -> Option<User> {
+    match user_id_by_twitter_handle(db, twitter_handle).await {
+        Some(id) => user_by_id(db, id).await,
+        None => None,
+    }
+}
+
+/// Every handle a user has been archived under, most recent first.
+pub async fn user_aliases(db: &DatabaseConnection, user_id: i64) -> Vec<String> {
+    UserAliases::find()
+        .filter(user_aliases::Column::UserId.eq(user_id))
+        .order_by_desc(user_aliases::Column::LastSeenAt)
+        .all(db)
+        .await
+        .expect(&format!("Failed to get the handles of user {user_id}"))
+        .into_iter()
+        .map(|alias| alias.username)
+        .collect::<Vec<String>>()
+}
+
+pub async fn latest_user_snapshot(db: &DatabaseConnection, user_id: i64) -> Option<UserSnapshotData> {
+    UserSnapshots::find()
+        .filter(user_snapshots::Column::UserId.eq(user_id))
+        .order_by_desc(user_snapshots::Column::FetchedAt)
+        .one(db)
+        .await
+        .expect("Failed to get latest user snapshot")
+        .map(|snapshot| snapshot.to_user_snapshot_data())
+}
+
+pub async fn user_snapshots(db: &DatabaseConnection, user_id: i64) -> Vec<UserSnapshotData> {
+    UserSnapshots::find()
+        .filter(user_snapshots::Column::UserId.eq(user_id))
+        .order_by_asc(user_snapshots::Column::FetchedAt)
+        .all(db)
+        .await
+        .expect(&format!("Failed to get the profile history of user {user_id}"))
+        .into_iter()
+        .map(|snapshot| snapshot.to_user_snapshot_data())
+        .collect::<Vec<UserSnapshotData>>()
+}
+
+/// The accounts the archive follows in the order they were added, each with
+/// the number of their tweets archived so far.
+pub async fn tracked_accounts(db: &DatabaseConnection) -> Vec<TrackedAccountData> {
+    let tracked_accounts = TrackedAccounts::find()
+        .find_also_related(Users)
+        .order_by_asc(tracked_accounts::Column::AddedAt)
+        .all(db)
+        .await
+        .expect("Failed to get tracked accounts");
+
+    let mut tracked_account_data = Vec::new();
+    for (tracked_account, user) in tracked_accounts {
+        if let Some(user) = user {
+            let tweet_count = Tweets::find()
+                .filter(tweets::Column::AuthorId.eq(tracked_account.user_id))
+                .count(db)
+                .await
+                .expect("Failed to count tracked account's tweets");
+            tracked_account_data.push(TrackedAccountData {
+                user: user.to_twitter_user(),
+                tweet_count,
+                added_at: tracked_account.added_at,
+                last_synced_at: tracked_account.last_synced_at,
+            });
+        }
+    }
+    tracked_account_data
+}
+
 pub async fn tweets(db: &DatabaseConnection) -> Vec<Tweet> {
     let db = db as &DatabaseConnection;
 
@@ -104,8 +265,11 @@ pub async fn users(db: &DatabaseConnection) -> Vec<User> {
 }
 
 pub async fn users_tweets(db: &DatabaseConnection, twitter_handle: &str) -> Vec<Tweet> {
-    let user = load_user_from_twitter_handle(db, twitter_handle).await;
-    let username = user.name;
+    let user = match load_user_from_twitter_handle(db, twitter_handle).await {
+        Some(user) => user,
+        None => return Vec::new(),
+    };
+    let username = user.username;
 
     let db = db as &DatabaseConnection;
 
@@ -164,30 +328,332 @@ pub async fn latest_tweet_from_user_by_twitter_handle(
     db: &DatabaseConnection,
     twitter_handle: &str,
 ) -> Option<Tweet> {
-    let db = db as &DatabaseConnection;
+    match user_id_by_twitter_handle(db, twitter_handle).await {
+        Some(id) => latest_tweet_from_user(db, id).await,
+        None => None,
+    }
+}
 
-    let res = Tweets::find()
-        .join(JoinType::InnerJoin, tweets::Relation::Users.def())
-        .filter(users::Column::Username.eq(twitter_handle))
-        .order_by_desc(tweets::Column::CreatedAt)
-        .one(db)
-        .await
-        .expect("Failed to get tweet model");
+/// Runs a parsed search box query, returning each match with a snippet around
+/// the matched terms. On SQLite text terms go through the `tweets_fts` index
+/// and can be ranked by relevance, other backends fall back to substring
+/// matching and rank the newest tweets first.
+pub async fn search_tweets_in_db(
+    db: &DatabaseConnection,
+    search_query: &SearchQuery,
+    options: SearchOptions,
+) -> Vec<(Tweet, Option<String>)> {
+    let backend = db.get_database_backend();
+    let search_query = &match options.is_fuzzy {
+        true => search_query.with_variants(&spelling_variants(db, search_query).await),
+        false => search_query.clone(),
+    };
+    let mut condition = Condition::all();
+
+    if !search_query.from.is_empty() {
+        let author_ids = user_ids_by_twitter_handles(db, &search_query.from).await;
+        condition = condition.add(tweets::Column::AuthorId.is_in(author_ids));
+    }
 
-    match res {
-        Some(tweet_model) => Some(tweet_model.to_tweet()),
-        None => None,
+    if !search_query.to.is_empty() {
+        let recipient_ids = user_ids_by_twitter_handles(db, &search_query.to).await;
+        // tweets archived before in_reply_to_user_id was stored only reveal
+        // who they answer through the leading mention
+        let to_condition = search_query.to.iter().fold(
+            Condition::any().add(tweets::Column::InReplyToUserId.is_in(recipient_ids)),
+            |to_condition, handle| {
+                let mention = escape_like(&format!("@{} ", handle));
+                to_condition.add(content_like(format!("{}%", mention)))
+            },
+        );
+        condition = condition.add(to_condition);
+    }
+
+    if let Some(since) = search_query.since {
+        condition = condition.add(tweets::Column::CreatedAt.gte(date_value(backend, since)));
+    }
+
+    if let Some(until) = search_query.until {
+        condition = condition.add(tweets::Column::CreatedAt.lt(date_value(backend, until)));
+    }
+
+    for (filter, is_required) in &search_query.filters {
+        let filter_condition = match filter {
+            // a reply's conversation started with some other tweet
+            Filter::Reply => Condition::any()
+                .add(tweets::Column::InReplyToUserId.is_not_null())
+                .add(Expr::cust("tweets.conversation_id <> tweets.id")),
+            Filter::Thread => Condition::all().add(Expr::cust(
+                "EXISTS (SELECT 1 FROM tweets AS thread \
+                 WHERE thread.conversation_id = tweets.conversation_id \
+                 AND thread.author_id = tweets.author_id AND thread.id <> tweets.id)",
+            )),
+            // the url entities Twitter found, a link spelled out in the text
+            // isn't one unless Twitter linked it
+            Filter::Link => Condition::all().add(tweets::Column::HasLinks.eq(true)),
+        };
+        condition = condition.add(if *is_required {
+            filter_condition
+        } else {
+            filter_condition.not()
+        });
+    }
+
+    let fts_match = match backend {
+        DbBackend::Sqlite => {
+            for term in &search_query.excluded_terms {
+                condition = condition.add(Expr::cust_with_values(
+                    "tweets.id NOT IN (SELECT rowid FROM tweets_fts WHERE tweets_fts MATCH ?)",
+                    vec![format!("content : {}", to_fts5_term(term))],
+                ));
+            }
+            to_fts5_match(&search_query.term_groups)
+        }
+        _ => {
+            for group in &search_query.term_groups {
+                condition = condition.add(group.iter().fold(Condition::any(), |group, term| {
+                    group.add(content_like(format!("%{}%", escape_like(term.text()))))
+                }));
+            }
+            for term in &search_query.excluded_terms {
+                condition = condition.add(
+                    Condition::all()
+                        .add(content_like(format!("%{}%", escape_like(term.text()))))
+                        .not(),
+                );
+            }
+            None
+        }
+    };
+
+    match fts_match {
+        Some(fts_match) => {
+            condition = condition.add(Expr::cust_with_values(
+                "tweets.id IN (SELECT rowid FROM tweets_fts WHERE tweets_fts MATCH ?)",
+                vec![fts_match.clone()],
+            ));
+            let query = Tweets::find().filter(condition);
+            let query = match options.order {
+                // bm25 scores are negative, the lowest is the best match
+                SearchOrder::Relevance => query.order_by(
+                    Expr::cust_with_values(
+                        &format!(
+                            "(SELECT bm25(tweets_fts, {:.1}, {:.1}) FROM tweets_fts \
+                             WHERE tweets_fts MATCH ? AND tweets_fts.rowid = tweets.id)",
+                            CONTENT_RANK_WEIGHT, AUTHOR_RANK_WEIGHT
+                        ),
+                        vec![fts_match.clone()],
+                    ),
+                    Order::Asc,
+                ),
+                SearchOrder::Newest => query.order_by(tweets::Column::CreatedAt, Order::Desc),
+                SearchOrder::Oldest => query.order_by(tweets::Column::CreatedAt, Order::Asc),
+            };
+            let results = query
+                .limit(SEARCH_RESULT_LIMIT)
+                .all(db)
+                .await
+                .expect("Failed to run tweet search");
+            let mut snippets = full_text_search_snippets(
+                db,
+                &fts_match,
+                &results.iter().map(|b| b.id).collect::<Vec<i64>>(),
+            )
+            .await;
+            results
+                .into_iter()
+                .map(|b| {
+                    let snippet = snippets.remove(&b.id);
+                    (b.to_tweet(), snippet)
+                })
+                .collect()
+        }
+        None => Tweets::find()
+            .filter(condition)
+            .order_by(
+                tweets::Column::CreatedAt,
+                match options.order {
+                    SearchOrder::Oldest => Order::Asc,
+                    SearchOrder::Relevance | SearchOrder::Newest => Order::Desc,
+                },
+            )
+            .limit(SEARCH_RESULT_LIMIT)
+            .all(db)
+            .await
+            .expect("Failed to run tweet search")
+            .into_iter()
+            .map(|b| (b.to_tweet(), None))
+            .collect::<Vec<(Tweet, Option<String>)>>(),
     }
 }
 
-pub async fn search_tweets_in_db(db: &DatabaseConnection, search_query: &str) -> Vec<Tweet> {
-    Tweets::find()
-        .filter(tweets::Column::Content.contains(search_query))
-        .order_by_desc(tweets::Column::CreatedAt)
-        .all(db)
-        .await
-        .expect("Failed to run tweet search")
+/// Archived words within typing distance of `word`, closest and then most
+/// used first, paired with their distance. The word itself comes first with a
+/// distance of 0 when it is in the archive. Only SQLite keeps the vocabulary
+/// these come from, other backends never find any.
+pub async fn similar_words(db: &DatabaseConnection, word: &str) -> Vec<(String, usize)> {
+    if db.get_database_backend() != DbBackend::Sqlite {
+        return Vec::new();
+    }
+
+    let word = word.to_lowercase();
+    let word_length = word.chars().count();
+    let allowance = typo_allowance(&word);
+    let vocabulary = ArchivedWord::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        "SELECT term, doc FROM tweet_words WHERE length(term) BETWEEN ? AND ?",
+        vec![
+            (word_length.saturating_sub(allowance) as i64).into(),
+            ((word_length + allowance) as i64).into(),
+        ],
+    ))
+    .all(db)
+    .await
+    .unwrap_or_else(|e| {
+        println!("Failed to read the archived words because {}", e);
+        Vec::new()
+    });
+
+    let mut similar_words: Vec<(usize, i64, String)> = vocabulary
         .into_iter()
-        .map(|b| b.to_tweet())
-        .collect::<Vec<twitter_v2::Tweet>>()
+        .filter_map(|archived_word| {
+            let distance = edit_distance(&word, &archived_word.term);
+            (distance <= allowance).then(|| (distance, archived_word.doc, archived_word.term))
+        })
+        .collect();
+    similar_words.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    similar_words
+        .into_iter()
+        .map(|(distance, _, term)| (term, distance))
+        .collect()
+}
+
+/// The misspellings and respellings of each searched word found in the archive.
+async fn spelling_variants(
+    db: &DatabaseConnection,
+    search_query: &SearchQuery,
+) -> HashMap<String, Vec<String>> {
+    let mut variants = HashMap::new();
+    for word in search_query.words() {
+        let similar_words = similar_words(db, &word)
+            .await
+            .into_iter()
+            .map(|(similar_word, _)| similar_word)
+            .filter(|similar_word| *similar_word != word)
+            .collect();
+        variants.insert(word, similar_words);
+    }
+    variants
+}
+
+#[derive(Debug, FromQueryResult)]
+struct ArchivedWord {
+    term: String,
+    doc: i64,
+}
+
+async fn user_ids_by_twitter_handles(db: &DatabaseConnection, twitter_handles: &[String]) -> Vec<i64> {
+    let mut user_ids = Vec::new();
+    for twitter_handle in twitter_handles {
+        if let Some(user_id) = user_id_by_twitter_handle(db, twitter_handle).await {
+            user_ids.push(user_id);
+        }
+    }
+    user_ids
+}
+
+// SQLite keeps timestamps as text, which only orders correctly against a bare
+// date, while the other backends need a real timestamp.
+fn date_value(backend: DbBackend, date: NaiveDate) -> Value {
+    match backend {
+        DbBackend::Sqlite => date.format("%Y-%m-%d").to_string().into(),
+        _ => DateTime::<FixedOffset>::from_utc(date.and_hms(0, 0, 0), FixedOffset::east(0)).into(),
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct FullTextSearchSnippet {
+    id: i64,
+    snippet: String,
+}
+
+/// Snippets around the matched terms, only for the given tweets so a broad
+/// search doesn't build one for every match.
+async fn full_text_search_snippets(
+    db: &DatabaseConnection,
+    fts_match: &str,
+    tweet_ids: &[i64],
+) -> HashMap<i64, String> {
+    if tweet_ids.is_empty() {
+        return HashMap::new();
+    }
+    let snippets = FullTextSearchSnippet::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        &format!(
+            "SELECT rowid AS id, snippet(tweets_fts, 0, '{}', '{}', '…', 16) AS snippet \
+             FROM tweets_fts WHERE tweets_fts MATCH ? AND rowid IN ({})",
+            SNIPPET_HIGHLIGHT_START,
+            SNIPPET_HIGHLIGHT_END,
+            tweet_ids
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        vec![fts_match.into()],
+    ))
+    .all(db)
+    .await;
+
+    match snippets {
+        Ok(snippets) => snippets
+            .into_iter()
+            .map(|snippet| (snippet.id, snippet.snippet))
+            .collect(),
+        Err(e) => {
+            println!("Failed to run tweet search for {} because {}", fts_match, e);
+            HashMap::new()
+        }
+    }
+}
+
+/// Matches tweet text against a `LIKE` pattern escaped by `escape_like`.
+fn content_like(pattern: String) -> SimpleExpr {
+    Expr::cust_with_values("tweets.content LIKE ? ESCAPE '!'", vec![pattern])
+}
+
+/// Escapes the `LIKE` wildcards in text, so a handle like `some_user` doesn't
+/// also match `someXuser`. `!` is the escape character as a backslash would
+/// need escaping itself in MySQL string literals.
+fn escape_like(text: &str) -> String {
+    text.replace('!', "!!").replace('%', "!%").replace('_', "!_")
+}
+
+/// Builds an FTS5 expression requiring every group, where a group matches when
+/// any of its terms do.
+fn to_fts5_match(term_groups: &Vec<Vec<Term>>) -> Option<String> {
+    if term_groups.is_empty() {
+        return None;
+    }
+    Some(
+        term_groups
+            .iter()
+            .map(|group| {
+                let group_match = group
+                    .iter()
+                    .map(|term| to_fts5_term(term))
+                    .collect::<Vec<String>>()
+                    .join(" OR ");
+                format!("({})", group_match)
+            })
+            .collect::<Vec<String>>()
+            .join(" AND "),
+    )
+}
+
+/// Terms match the author's name and handle as well as the tweet text, so
+/// `pearl counterfactual` finds Judea Pearl's tweets about counterfactuals.
+/// Every term is quoted so punctuation in it can't be read as FTS5 syntax.
+fn to_fts5_term(term: &Term) -> String {
+    format!("\"{}\"", term.text().replace('"', "\"\""))
 }
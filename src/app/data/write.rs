@@ -1,38 +1,22 @@
-use super::super::super::utils::{convert_date_to_chrono, TweetReferenceData};
+use super::super::super::utils::{
+    convert_date_to_chrono, to_ron_column, TweetReferenceData, UserSnapshotData, WriteReport,
+};
 use super::entities::prelude::*;
 use super::entities::*;
-use crate::app::{load_tweet_from_id, load_user_from_id};
-use async_recursion::async_recursion;
-use futures::StreamExt;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
-use sea_orm::{ActiveValue, DatabaseConnection, EntityTrait};
-use twitter_v2::data::ReferencedTweet;
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, Insert, Iterable, QueryFilter, QueryTrait, TransactionTrait,
+};
 use twitter_v2::{Tweet, User};
 
-pub async fn tweet(db: &DatabaseConnection, tweet: &Tweet) -> () {
-    let author_id = tweet
-        .author_id
-        .expect("Bad author id")
-        .as_u64()
-        .try_into()
-        .expect("Failed to parse i64 from u64");
-
-    let conversation_id = tweet
-        .conversation_id
-        .expect("Bad conversation id")
-        .clone()
-        .as_u64()
-        .try_into()
-        .expect("Failed to parse i64 from u64");
+// Keeps each multi-row insert well under SQLite's limit on bound parameters
+const BULK_WRITE_CHUNK_SIZE: usize = 40;
 
-    load_user_from_id(&db, author_id).await;
-    if !super::read::does_conversation_exist(db, conversation_id).await {
-        conversation(db, &conversation_id).await;
-    }
-
-    let converted_offset_date = convert_date_to_chrono(tweet.created_at);
-
-    let to_write = tweets::ActiveModel {
+fn tweet_to_active_model(tweet: &Tweet) -> tweets::ActiveModel {
+    tweets::ActiveModel {
         id: ActiveValue::set(tweet.id.as_u64().try_into().expect("Bad tweet id")),
         conversation_id: ActiveValue::set(
             tweet
@@ -51,162 +35,380 @@ pub async fn tweet(db: &DatabaseConnection, tweet: &Tweet) -> () {
                 .try_into()
                 .expect("Failed to parse i64 from u64"),
         ),
-        created_at: ActiveValue::set(converted_offset_date),
-    };
-
-    let res = Tweets::insert(to_write).exec(db).await;
-
-    match res {
-        Ok(res) => (),
-        Err(e) => println!(
-            "Failed to to write tweet {} to the database because {}",
-            tweet.id, e
+        created_at: ActiveValue::set(convert_date_to_chrono(tweet.created_at)),
+        attachments: ActiveValue::set(to_ron_column(&tweet.attachments)),
+        context_annotations: ActiveValue::set(to_ron_column(&tweet.context_annotations)),
+        entities: ActiveValue::set(to_ron_column(&tweet.entities)),
+        geo: ActiveValue::set(to_ron_column(&tweet.geo)),
+        in_reply_to_user_id: ActiveValue::set(tweet.in_reply_to_user_id.map(|id| {
+            id.as_u64()
+                .try_into()
+                .expect("Failed to parse i64 from u64")
+        })),
+        lang: ActiveValue::set(tweet.lang.clone()),
+        non_public_metrics: ActiveValue::set(to_ron_column(&tweet.non_public_metrics)),
+        organic_metrics: ActiveValue::set(to_ron_column(&tweet.organic_metrics)),
+        possibly_sensitive: ActiveValue::set(tweet.possibly_sensitive),
+        promoted_metrics: ActiveValue::set(to_ron_column(&tweet.promoted_metrics)),
+        public_metrics: ActiveValue::set(to_ron_column(&tweet.public_metrics)),
+        referenced_tweets: ActiveValue::set(to_ron_column(&tweet.referenced_tweets)),
+        reply_settings: ActiveValue::set(to_ron_column(&tweet.reply_settings)),
+        source: ActiveValue::set(tweet.source.clone()),
+        withheld: ActiveValue::set(to_ron_column(&tweet.withheld)),
+        has_links: ActiveValue::set(
+            tweet
+                .entities
+                .as_ref()
+                .and_then(|entities| entities.urls.as_ref())
+                .map_or(false, |urls| !urls.is_empty()),
         ),
     }
 }
 
-pub async fn tweet_with_reference(db: &DatabaseConnection, tweet: &Tweet) -> () {
-    let tweet_id: i64 = tweet
-        .id
-        .as_u64()
-        .try_into()
-        .expect("Failed to parse i64 from u64");
-
-    let author_id = tweet
-        .author_id
-        .expect("Bad author id")
-        .as_u64()
-        .try_into()
-        .expect("Failed to parse i64 from u64");
+/// Inserts the row or, when one with the same key is already stored,
+/// overwrites the columns named in `on_conflict`. Writing the same data twice
+/// is harmless and re-synced rows pick up changes such as new metrics.
+async fn upsert<A, C>(db: &C, mut insert: Insert<A>, on_conflict: OnConflict) -> Result<(), DbErr>
+where
+    A: ActiveModelTrait,
+    C: ConnectionTrait,
+{
+    insert.query().on_conflict(on_conflict);
+    db.execute(insert.build(db.get_database_backend()))
+        .await
+        .map(|_| ())
+}
 
-    let conversation_id = tweet
-        .conversation_id
-        .expect("Bad conversation id")
-        .clone()
-        .as_u64()
-        .try_into()
-        .expect("Failed to parse i64 from u64");
+fn tweet_on_conflict() -> OnConflict {
+    OnConflict::column(tweets::Column::Id)
+        .update_columns(
+            tweets::Column::iter().filter(|column| !matches!(column, tweets::Column::Id)),
+        )
+        .to_owned()
+}
 
-    load_user_from_id(&db, author_id).await;
-    if !super::read::does_conversation_exist(db, conversation_id).await {
-        conversation(db, &conversation_id).await;
+fn tweet_reference_to_active_model(
+    tweet_reference_data: &TweetReferenceData,
+) -> tweet_references::ActiveModel {
+    tweet_references::ActiveModel {
+        source_tweet_id: ActiveValue::Set(tweet_reference_data.source_tweet_id),
+        reference_type: ActiveValue::Set(tweet_reference_data.type_to_string()),
+        referenced_tweet_id: ActiveValue::Set(tweet_reference_data.reference_tweet_id),
     }
+}
 
-    let converted_offset_date = convert_date_to_chrono(tweet.created_at);
+fn tweet_reference_on_conflict() -> OnConflict {
+    OnConflict::columns([
+        tweet_references::Column::SourceTweetId,
+        tweet_references::Column::ReferenceType,
+    ])
+    .update_column(tweet_references::Column::ReferencedTweetId)
+    .to_owned()
+}
 
-    let to_write = tweets::ActiveModel {
-        id: ActiveValue::set(tweet.id.as_u64().try_into().expect("Bad tweet id")),
-        conversation_id: ActiveValue::set(
+/// Writes a batch of tweets and their references in one transaction, so an
+/// interrupted import leaves the archive as it was. Authors and conversations
+/// are resolved once up front, and tweets already archived without changes are
+/// skipped. Tweets whose author isn't archived can't be written and are handed
+/// back in the report, fetching authors is left to the caller. References are
+/// written for every tweet in the batch, which fills them in for tweets
+/// archived before they were recorded.
+pub async fn tweets(db: &DatabaseConnection, tweets: &Vec<Tweet>) -> Result<WriteReport, DbErr> {
+    let mut report = WriteReport::default();
+
+    // the last copy of a tweet in the batch is the freshest one
+    let mut seen_tweet_ids = HashSet::new();
+    let batch: Vec<&Tweet> = tweets
+        .iter()
+        .rev()
+        .filter(|tweet| seen_tweet_ids.insert(tweet.id.as_u64()))
+        .collect();
+
+    let author_id_of = |tweet: &Tweet| -> i64 {
+        tweet
+            .author_id
+            .expect("Bad author id")
+            .as_u64()
+            .try_into()
+            .expect("Failed to parse i64 from u64")
+    };
+    let author_ids: BTreeSet<i64> = batch.iter().map(|tweet| author_id_of(tweet)).collect();
+    let archived_author_ids: BTreeSet<i64> = Users::find()
+        .filter(users::Column::Id.is_in(author_ids.iter().cloned()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|user| user.id)
+        .collect();
+    // a tweet can't be written without its author, writing it anyway would
+    // fail the whole transaction
+    let (unique_tweets, tweets_without_author): (Vec<&Tweet>, Vec<&Tweet>) = batch
+        .into_iter()
+        .partition(|tweet| archived_author_ids.contains(&author_id_of(tweet)));
+    report.without_author = tweets_without_author.into_iter().cloned().collect();
+
+    let conversation_ids: Vec<i64> = unique_tweets
+        .iter()
+        .map(|tweet| {
             tweet
                 .conversation_id
                 .expect("Bad conversation id")
                 .as_u64()
                 .try_into()
-                .expect("Failed to parse i64 from u64"),
-        ),
-        content: ActiveValue::set(tweet.text.clone()),
-        author_id: ActiveValue::set(
-            tweet
-                .author_id
-                .expect("Bad author id")
-                .as_u64()
-                .try_into()
-                .expect("Failed to parse i64 from u64"),
-        ),
-        created_at: ActiveValue::set(converted_offset_date),
-    };
-
-    let res = Tweets::insert(to_write).exec(db).await;
+                .expect("Failed to parse i64 from u64")
+        })
+        .collect::<BTreeSet<i64>>()
+        .into_iter()
+        .collect();
 
-    match res {
-        Ok(res) => (),
-        Err(e) => println!(
-            "Failed to to write tweet {} to the database because {}",
-            tweet.id, e
-        ),
+    let tweet_ids: Vec<i64> = unique_tweets
+        .iter()
+        .map(|tweet| tweet.id.as_u64().try_into().expect("Bad tweet id"))
+        .collect();
+    let mut archived_tweets: HashMap<i64, Tweet> = HashMap::new();
+    for chunk in tweet_ids.chunks(BULK_WRITE_CHUNK_SIZE) {
+        for archived_tweet in Tweets::find()
+            .filter(tweets::Column::Id.is_in(chunk.iter().cloned()))
+            .all(db)
+            .await?
+        {
+            archived_tweets.insert(archived_tweet.id, archived_tweet.to_tweet());
+        }
     }
 
-    let referenced_tweets = tweet.referenced_tweets.clone();
+    let references: Vec<tweet_references::ActiveModel> = unique_tweets
+        .iter()
+        .zip(&tweet_ids)
+        .flat_map(|(tweet, tweet_id)| {
+            tweet
+                .referenced_tweets
+                .iter()
+                .flatten()
+                .map(|referenced_tweet| {
+                    tweet_reference_to_active_model(&TweetReferenceData::from_referenced_tweet(
+                        *tweet_id,
+                        referenced_tweet,
+                    ))
+                })
+                .collect::<Vec<tweet_references::ActiveModel>>()
+        })
+        .collect();
 
-    match referenced_tweets {
-        Some(references) => tweet_references(db, tweet_id, references, author_id).await,
-        None => println!("No referenced tweets"),
+    let mut to_write = Vec::new();
+    for (tweet, tweet_id) in unique_tweets.into_iter().zip(tweet_ids) {
+        match archived_tweets.get(&tweet_id) {
+            Some(archived_tweet) if archived_tweet == tweet => report.unchanged += 1,
+            Some(_) => {
+                report.updated += 1;
+                to_write.push(tweet_to_active_model(tweet));
+            }
+            None => {
+                report.inserted += 1;
+                to_write.push(tweet_to_active_model(tweet));
+            }
+        }
     }
-}
 
-pub async fn tweets(db: &DatabaseConnection, tweets: &Vec<Tweet>) -> () {
-    let tweet_stream = futures::stream::iter(tweets.iter());
-    tweet_stream.for_each(|t| tweet(db, t)).await;
+    let txn = db.begin().await?;
+    for chunk in conversation_ids.chunks(BULK_WRITE_CHUNK_SIZE) {
+        upsert(
+            &txn,
+            Conversations::insert_many(chunk.iter().map(|conversation_id| {
+                conversations::ActiveModel {
+                    id: ActiveValue::Set(*conversation_id),
+                }
+            })),
+            OnConflict::column(conversations::Column::Id)
+                .update_column(conversations::Column::Id)
+                .to_owned(),
+        )
+        .await?;
+    }
+    for chunk in to_write.chunks(BULK_WRITE_CHUNK_SIZE) {
+        upsert(
+            &txn,
+            Tweets::insert_many(chunk.iter().cloned()),
+            tweet_on_conflict(),
+        )
+        .await?;
+    }
+    for chunk in references.chunks(BULK_WRITE_CHUNK_SIZE) {
+        upsert(
+            &txn,
+            TweetReferences::insert_many(chunk.iter().cloned()),
+            tweet_reference_on_conflict(),
+        )
+        .await?;
+    }
+    txn.commit().await?;
+
+    Ok(report)
 }
 
-pub async fn user(db: &DatabaseConnection, user: &User) -> () {
-    let to_write = users::ActiveModel {
+fn user_to_active_model(user: &User) -> users::ActiveModel {
+    users::ActiveModel {
         id: ActiveValue::Set(user.id.as_u64().try_into().unwrap()),
         name: ActiveValue::Set(user.name.clone()),
         username: ActiveValue::Set(user.username.clone()),
-        description: ActiveValue::Set(
-            user.description
-                .clone()
-                .expect("Failed to unwrap description"),
+        description: ActiveValue::Set(user.description.clone()),
+        created_at: ActiveValue::Set(
+            user.created_at
+                .map(|created_at| convert_date_to_chrono(Some(created_at))),
         ),
-    };
-    let res = Users::insert(to_write)
-        .exec(db)
-        .await
-        .expect("failed to insert user into database");
+        entities: ActiveValue::Set(to_ron_column(&user.entities)),
+        location: ActiveValue::Set(user.location.clone()),
+        pinned_tweet_id: ActiveValue::Set(user.pinned_tweet_id.map(|id| {
+            id.as_u64()
+                .try_into()
+                .expect("Failed to parse i64 from u64")
+        })),
+        profile_image_url: ActiveValue::Set(
+            user.profile_image_url.as_ref().map(|url| url.to_string()),
+        ),
+        protected: ActiveValue::Set(user.protected),
+        public_metrics: ActiveValue::Set(to_ron_column(&user.public_metrics)),
+        url: ActiveValue::Set(user.url.as_ref().map(|url| url.to_string())),
+        verified: ActiveValue::Set(user.verified),
+        withheld: ActiveValue::Set(to_ron_column(&user.withheld)),
+    }
 }
 
-pub async fn conversation(db: &DatabaseConnection, conversation_id: &i64) -> () {
-    let to_write = conversations::ActiveModel {
-        id: ActiveValue::Set(conversation_id.clone()),
-    };
-    let res = Conversations::insert(to_write)
-        .exec(db)
-        .await
-        .expect("failed to insert conversation {conversation_id} into database");
+pub async fn user(db: &DatabaseConnection, user: &User) -> () {
+    upsert(
+        db,
+        Users::insert(user_to_active_model(user)),
+        OnConflict::column(users::Column::Id)
+            .update_columns(
+                users::Column::iter().filter(|column| !matches!(column, users::Column::Id)),
+            )
+            .to_owned(),
+    )
+    .await
+    .expect("failed to write user to database");
 }
 
-#[async_recursion]
-pub async fn tweet_reference(
-    db: &DatabaseConnection,
-    tweet_reference_data: TweetReferenceData,
-    user_id: i64,
-) -> () {
-    let referenced_tweet_id = tweet_reference_data.reference_tweet_id;
-
-    if !super::read::does_tweet_exist(db, referenced_tweet_id.clone()).await {
-        load_tweet_from_id(db, referenced_tweet_id.clone(), user_id).await;
-    }
+/// Records that the user currently goes by their username, so the handle keeps
+/// resolving to them after they rename.
+pub async fn user_alias(db: &DatabaseConnection, user: &User) -> () {
+    let user_id: i64 = user
+        .id
+        .as_u64()
+        .try_into()
+        .expect("Failed to parse i64 from u64");
+    let handle = user.username.to_lowercase();
+    let now = chrono::Utc::now().with_timezone(&chrono::FixedOffset::east(0));
 
-    let to_write = tweet_references::ActiveModel {
-        source_tweet_id: ActiveValue::Set(tweet_reference_data.source_tweet_id.clone()),
-        reference_type: ActiveValue::Set(tweet_reference_data.type_to_string()),
-        referenced_tweet_id: ActiveValue::Set(referenced_tweet_id.clone()),
+    let to_write = user_aliases::ActiveModel {
+        handle: ActiveValue::Set(handle),
+        user_id: ActiveValue::Set(user_id),
+        username: ActiveValue::Set(user.username.clone()),
+        first_seen_at: ActiveValue::Set(now),
+        last_seen_at: ActiveValue::Set(now),
     };
-    let res = TweetReferences::insert(to_write).exec(db).await;
+    let res = upsert(
+        db,
+        UserAliases::insert(to_write),
+        OnConflict::columns([user_aliases::Column::Handle, user_aliases::Column::UserId])
+            .update_columns([
+                user_aliases::Column::Username,
+                user_aliases::Column::LastSeenAt,
+            ])
+            .to_owned(),
+    )
+    .await;
 
     match res {
-        Ok(_res) => (),
+        Ok(()) => (),
         Err(e) => println!(
-            "Failed to add tweet reference {} to the database because of {}",
-            referenced_tweet_id, e
+            "Failed to record @{} as a handle of user {} because {}",
+            user.username, user.id, e
         ),
     }
 }
 
-pub async fn tweet_references(
-    db: &DatabaseConnection,
-    tweet_id: i64,
-    tweet_references: Vec<ReferencedTweet>,
-    user_id: i64,
-) -> () {
-    let tweet_reference_stream = futures::stream::iter(tweet_references.iter());
-    tweet_reference_stream
-        .for_each(|tweet_ref| async {
-            let tweet_reference_data =
-                TweetReferenceData::from_referenced_tweet(tweet_id, tweet_ref);
-
-            tweet_reference(db, tweet_reference_data, user_id).await
-        })
+/// Adds the user to the accounts the archive follows. Tracking an account twice
+/// leaves it as it was.
+pub async fn tracked_account(db: &DatabaseConnection, user_id: i64) -> () {
+    let to_write = tracked_accounts::ActiveModel {
+        user_id: ActiveValue::Set(user_id),
+        added_at: ActiveValue::Set(chrono::Utc::now().with_timezone(&chrono::FixedOffset::east(0))),
+        last_synced_at: ActiveValue::Set(None),
+    };
+    let res = upsert(
+        db,
+        TrackedAccounts::insert(to_write),
+        OnConflict::column(tracked_accounts::Column::UserId)
+            .update_column(tracked_accounts::Column::UserId)
+            .to_owned(),
+    )
+    .await;
+
+    match res {
+        Ok(()) => (),
+        Err(e) => println!("Failed to track user {} because {}", user_id, e),
+    }
+}
+
+/// Stops following the user. Their archived tweets are kept.
+pub async fn untracked_account(db: &DatabaseConnection, user_id: i64) -> () {
+    let res = TrackedAccounts::delete_by_id(user_id).exec(db).await;
+
+    match res {
+        Ok(_) => (),
+        Err(e) => println!("Failed to stop tracking user {} because {}", user_id, e),
+    }
+}
+
+/// Notes that the user's tweets were just checked with the server. Users who
+/// aren't tracked are left alone.
+pub async fn tracked_account_synced(db: &DatabaseConnection, user_id: i64) -> () {
+    let now = chrono::Utc::now().with_timezone(&chrono::FixedOffset::east(0));
+    let res = TrackedAccounts::update_many()
+        .col_expr(tracked_accounts::Column::LastSyncedAt, Expr::value(now))
+        .filter(tracked_accounts::Column::UserId.eq(user_id))
+        .exec(db)
         .await;
+
+    match res {
+        Ok(_) => (),
+        Err(e) => println!("Failed to record the sync of user {} because {}", user_id, e),
+    }
+}
+
+/// Appends the user's current profile to their history, unless nothing changed
+/// since the last snapshot.
+pub async fn user_snapshot(db: &DatabaseConnection, user: &User) -> () {
+    let fetched_at = chrono::Utc::now().with_timezone(&chrono::FixedOffset::east(0));
+    let snapshot = UserSnapshotData::from_user(user, fetched_at);
+
+    match super::read::latest_user_snapshot(db, snapshot.user_id).await {
+        Some(latest) if latest.is_same_profile(&snapshot) => (),
+        _ => {
+            let to_write = user_snapshots::ActiveModel {
+                user_id: ActiveValue::Set(snapshot.user_id),
+                fetched_at: ActiveValue::Set(snapshot.fetched_at),
+                name: ActiveValue::Set(snapshot.name),
+                username: ActiveValue::Set(snapshot.username),
+                description: ActiveValue::Set(snapshot.description),
+                location: ActiveValue::Set(snapshot.location),
+                url: ActiveValue::Set(snapshot.url),
+                profile_image_url: ActiveValue::Set(snapshot.profile_image_url),
+                followers_count: ActiveValue::Set(snapshot.followers_count),
+                following_count: ActiveValue::Set(snapshot.following_count),
+                tweet_count: ActiveValue::Set(snapshot.tweet_count),
+                listed_count: ActiveValue::Set(snapshot.listed_count),
+                verified: ActiveValue::Set(snapshot.verified),
+                protected: ActiveValue::Set(snapshot.protected),
+                ..Default::default()
+            };
+
+            match UserSnapshots::insert(to_write).exec(db).await {
+                Ok(_res) => (),
+                Err(e) => println!(
+                    "Failed to write a profile snapshot of user {} to the database because {}",
+                    user.id, e
+                ),
+            }
+        }
+    }
 }
@@ -1,159 +1,247 @@
-use chrono::DateTime;
-use reqwest;
-use ron;
-use std::process::Command;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 use time::OffsetDateTime;
 use twitter_v2::{Tweet, User};
 
-use crate::utils;
-const API_PROD: &str = "https://judea-pearl-tweets-archive.onrender.com/";
-const API: &str = API_PROD;
+pub mod fixture;
+pub mod ron_api;
+pub mod twitter_api;
+
+pub use fixture::FixtureSource;
+pub use ron_api::RonApiSource;
+pub use twitter_api::TwitterApiSource;
+
+pub const API_PROD: &str = "https://judea-pearl-tweets-archive.onrender.com/";
 
 //const API_PROD: &str = "https://better-twitter-archiver.onrender.com/";
 //const API_DEV: &str = "http://127.0.0.1:8000/";
-pub fn get_tweets_from_twitter_handle(twitter_handle: &str) -> Vec<Tweet> {
-    let resp = reqwest::blocking::get(format!("{API}user/{twitter_handle}/tweets"))
-        .expect(&format!("failed to get @{twitter_handle}'s tweets"));
-    let tweet_ron_str = resp.text().expect(&format!(
-        "failed to parse text from @{twitter_handle}'s tweets"
-    ));
-    let tweets: Vec<Tweet> = ron::from_str(&tweet_ron_str).expect(&format!(
-        "Failed to parse @{twitter_handle}'s tweets data into Rusty Object Notation"
-    ));
-    tweets
-}
-
-pub fn get_conversation_by_tweet_id(id: i64) -> Vec<Tweet> {
-    let resp = reqwest::blocking::get(format!("{API}conversation/{id}")).expect(&format!(
-        "failed to get conversation ending in tweet of id {id}"
-    ));
-    let conversation_ron_str = resp.text().expect(&format!(
-        "failed to parse text for conversation ending in tweet of id @{id}'s tweets"
-    ));
-
-    ron::from_str(&conversation_ron_str)
-                .expect(&format!("Failed to parse conversation ending in tweet of id {id}'s tweets data into Rusty Object Notation"))
-}
-
-pub fn get_tweet_by_id(id: i64) -> Option<Tweet> {
-    let request = reqwest::blocking::get(format!("{API}tweet/{id}"))
-        .unwrap_or_else(|error| panic!("Failed to get request from server. Error {:?}", error));
-    let response = request
-        .text()
-        .unwrap_or_else(|error| panic!("Couldn't get server text. Error: {:?}", error));
-    ron::from_str(&response).unwrap_or_else(|error| {
-        println!("Failed to get tweet from server, waiting 15 minutes before trying again. Error: {:?}", error);
-        Command::new("sleep")
-            .arg("910")
-            .spawn()
-            .unwrap()
-            .wait()
-            .unwrap();
-        let request = reqwest::blocking::get(format!("{API}tweet/{id}"))
-            .unwrap_or_else(|error| panic!("(Second attempt) Failed to get request from server. Error {:?}", error));
-        let response = request
-            .text()
-            .unwrap_or_else(|error| panic!("(Second attempt) Couldn't get server text. Error: {:?}", error));
-        ron::from_str(&response).unwrap_or_else(|error| {
-            panic!("Second Attempt: Failed to get tweet from server even after waiting fifteen minutes. Error: {:?}", error);
-        })
-    })
-    // match reqwest::blocking::get(format!("{API}tweet/{id}")) {
-    //     Ok(server_response) => match server_response.text() {
-    //         Ok(text_from_server) => match ron::from_str(&text_from_server) {
-    //             Ok(option_tweet) => option_tweet,
-    //             Err(e) => {
-    //                 println!(
-    //                     "Failed to get tweet from server, waiting 15 minutes before trying again"
-    //                 );
-    //                 Command::new("sleep")
-    //                     .arg("910")
-    //                     .spawn()
-    //                     .unwrap()
-    //                     .wait()
-    //                     .unwrap();
-    //                 match reqwest::blocking::get(format!("{API}tweet/{id}")) {
-    //                     Ok(server_response) => match server_response.text() {
-    //                         Ok(text_from_server) => match ron::from_str(&text_from_server) {
-    //                             Ok(option_tweet) => option_tweet,
-    //                             Err(e) => {
-    //                                 panic!("Failed on second attempt to parse server response text into tweet")
-    //                             }
-    //                         },
-    //                         Err(e) => {
-    //                             panic!("Failed to get text from server on second try. Error {e}")
-    //                         }
-    //                     },
-    //                     Err(e) => {
-    //                         panic!("Server response failed on second try. Error {e}")
-    //                     }
-    //                 }
-    //             }
-    //         },
-    //         Err(e) => {
-    //             panic!("Couldn't get the server text. Error: {e}")
-    //         }
-    //     },
-    //     Err(e) => {
-    //         panic!("Server response failed. Error: {e}")
-    //     }
-    // }
-}
-
-pub fn get_user_by_id(id: u64) -> User {
-    let resp = reqwest::blocking::get(format!("{API}userbyid/{id}"))
-        .expect(&format!("failed to get user from id {id}"));
-    let user_ron_str = resp.text().expect(&format!(
-        "failed to parse text from response to request for user of id {id}'s data"
-    ));
-    ron::from_str(&user_ron_str).expect(&format!(
-        "Failed to parse user of id {id}'s data into Rusty Object Notation",
-    ))
-}
-
-pub fn get_user_by_twitter_handle(twitter_handle: &str) -> User {
-    let resp = reqwest::blocking::get(format!("{API}user/{twitter_handle}/info")).expect(&format!(
-        "failed to get user from twitter handle @{twitter_handle}"
-    ));
-    let user_ron_str = resp
-        .text()
-        .expect("failed to parse text from response for user @{twitter_handle}");
-    ron::from_str(&user_ron_str).expect(&format!(
-        "Failed to parse @{twitter_handle} user data into Rusty Object Notation"
-    ))
-}
-
-pub fn find_out_if_user_has_tweeted_since(twitter_handle: &str, since: &OffsetDateTime) -> bool {
-    let since = utils::convert_date_to_chrono(Some(since.clone()));
-    let rfc3339_datestring = since.to_rfc3339();
-    let resp = reqwest::blocking::get(format!(
-        "{API}user/{twitter_handle}/has_tweeted_since/{rfc3339_datestring}"
-    ))
-    .expect(&format!(
-        "failed to get user from twitter handle @{twitter_handle}"
-    ));
-    let user_ron_str = resp
-        .text()
-        .expect("failed to parse text from response for user @{twitter_handle}");
-    ron::from_str(&user_ron_str).expect(&format!(
-        "Failed to parse @{twitter_handle} user data into Rusty Object Notation"
-    ))
-}
-
-pub fn get_users_tweets_since(twitter_handle: &str, since: &OffsetDateTime) -> Vec<Tweet> {
-    let since = utils::convert_date_to_chrono(Some(since.clone()));
-    let rfc3339_datestring = since.to_rfc3339();
-
-    let resp = reqwest::blocking::get(format!(
-        "{API}user/{twitter_handle}/tweets-since/{rfc3339_datestring}"
-    ))
-    .expect(&format!(
-        "failed to get user from twitter handle @{twitter_handle}"
-    ));
-    let user_ron_str = resp
-        .text()
-        .expect("failed to parse text from response for user @{twitter_handle}");
-    ron::from_str(&user_ron_str).expect(&format!(
-        "Failed to parse @{twitter_handle} user data into Rusty Object Notation"
-    ))
+
+/// Set while the archive works from the database alone, either because it was
+/// asked to or because the server couldn't be reached.
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Where tweets and users come from, the RON API at `API_PROD` unless
+/// `set_source` picked another.
+static SOURCE: RwLock<Option<Arc<dyn ArchiveSource>>> = RwLock::new(None);
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+pub fn set_offline(is_offline: bool) {
+    OFFLINE.store(is_offline, Ordering::Relaxed);
+}
+
+pub fn set_source(source: impl ArchiveSource + 'static) {
+    *SOURCE.write().expect("Failed to lock the archive source") = Some(Arc::new(source));
+}
+
+fn source() -> Arc<dyn ArchiveSource> {
+    if let Some(source) = SOURCE.read().expect("Failed to lock the archive source").as_ref() {
+        return source.clone();
+    }
+    // only the first request installs the default
+    SOURCE
+        .write()
+        .expect("Failed to lock the archive source")
+        .get_or_insert_with(|| Arc::new(RonApiSource::new(API_PROD)))
+        .clone()
+}
+
+/// Everything the archive asks of the outside world. Implementations fetch
+/// and nothing else, archiving what they return and going offline is left
+/// to the functions below.
+pub trait ArchiveSource: Send + Sync {
+    fn user_by_id(&self, id: u64) -> Result<User, ServerError>;
+
+    fn user_by_twitter_handle(&self, twitter_handle: &str) -> Result<User, ServerError>;
+
+    fn users_tweets(&self, twitter_handle: &str) -> Result<Vec<Tweet>, ServerError>;
+
+    fn users_tweets_since(
+        &self,
+        twitter_handle: &str,
+        since: &OffsetDateTime,
+    ) -> Result<Vec<Tweet>, ServerError>;
+
+    fn has_user_tweeted_since(
+        &self,
+        twitter_handle: &str,
+        since: &OffsetDateTime,
+    ) -> Result<bool, ServerError>;
+
+    /// The tweets of the conversation that `id` starts.
+    fn conversation(&self, id: i64) -> Result<Vec<Tweet>, ServerError>;
+
+    /// `None` when the tweet doesn't exist, usually because it was deleted.
+    fn tweet_by_id(&self, id: i64) -> Result<Option<Tweet>, ServerError>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerError {
+    /// Offline mode is on so nothing was requested
+    Offline,
+    /// The request didn't go through, which turns offline mode on
+    Unreachable(String),
+    /// The server answered with something other than what was asked for
+    BadResponse(String),
+    /// The server kept refusing requests for now, which turns offline mode on
+    RateLimited(String),
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerError::Offline => write!(f, "the archive is offline"),
+            ServerError::Unreachable(error) => {
+                write!(f, "the server couldn't be reached: {}", error)
+            }
+            ServerError::BadResponse(error) => {
+                write!(f, "the server's response was unreadable: {}", error)
+            }
+            ServerError::RateLimited(error) => {
+                write!(f, "the server is refusing requests for now: {}", error)
+            }
+        }
+    }
+}
+
+/// Makes a request through the current source unless offline, and goes
+/// offline when the source can't be reached or won't answer for now.
+fn request<T>(
+    call: impl FnOnce(&dyn ArchiveSource) -> Result<T, ServerError>,
+) -> Result<T, ServerError> {
+    if is_offline() {
+        return Err(ServerError::Offline);
+    }
+    let result = call(source().as_ref());
+    match &result {
+        Err(ServerError::Unreachable(error)) => {
+            println!(
+                "Going offline, the server couldn't be reached. Error: {}",
+                error
+            );
+            set_offline(true);
+        }
+        Err(ServerError::RateLimited(error)) => {
+            println!("Going offline, the server is rate limiting. Error: {}", error);
+            set_offline(true);
+        }
+        _ => (),
+    }
+    result
+}
+
+pub fn get_tweets_from_twitter_handle(twitter_handle: &str) -> Result<Vec<Tweet>, ServerError> {
+    request(|source| source.users_tweets(twitter_handle))
+}
+
+pub fn get_conversation_by_tweet_id(id: i64) -> Result<Vec<Tweet>, ServerError> {
+    request(|source| source.conversation(id))
+}
+
+/// Sources handle their own rate limits, so a failure here is returned as is.
+pub fn get_tweet_by_id(id: i64) -> Result<Option<Tweet>, ServerError> {
+    request(|source| source.tweet_by_id(id))
+}
+
+pub fn get_user_by_id(id: u64) -> Result<User, ServerError> {
+    request(|source| source.user_by_id(id))
+}
+
+pub fn get_user_by_twitter_handle(twitter_handle: &str) -> Result<User, ServerError> {
+    request(|source| source.user_by_twitter_handle(twitter_handle))
+}
+
+pub fn find_out_if_user_has_tweeted_since(
+    twitter_handle: &str,
+    since: &OffsetDateTime,
+) -> Result<bool, ServerError> {
+    request(|source| source.has_user_tweeted_since(twitter_handle, since))
+}
+
+pub fn get_users_tweets_since(
+    twitter_handle: &str,
+    since: &OffsetDateTime,
+) -> Result<Vec<Tweet>, ServerError> {
+    request(|source| source.users_tweets_since(twitter_handle, since))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, MutexGuard};
+
+    /// Held by tests that set or depend on the global source and offline mode.
+    static GLOBAL_STATE: Mutex<()> = Mutex::new(());
+
+    pub(crate) fn lock_global_state() -> MutexGuard<'static, ()> {
+        // a failed test doesn't leave the state any less usable
+        GLOBAL_STATE.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    const USERS: &str = r#"[(id: "3363584909", name: "Judea Pearl", username: "yudapearl")]"#;
+
+    const TWEETS: &str = r#"[
+        (
+            id: "1012187366587392000",
+            text: "Hi everybody",
+            author_id: Some("3363584909"),
+            conversation_id: Some("1012187366587392000"),
+            created_at: Some("2018-06-28T04:14:08Z"),
+        ),
+        (
+            id: "1078627642667032576",
+            text: "1/2",
+            author_id: Some("3363584909"),
+            conversation_id: Some("1078627642667032576"),
+            created_at: Some("2018-12-28T12:24:04Z"),
+        ),
+        (
+            id: "1078627643417780226",
+            text: "2/2",
+            author_id: Some("3363584909"),
+            conversation_id: Some("1078627642667032576"),
+            created_at: Some("2018-12-28T12:24:05Z"),
+        ),
+    ]"#;
+
+    fn ids(tweets: Vec<Tweet>) -> Vec<u64> {
+        tweets.iter().map(|tweet| tweet.id.as_u64()).collect()
+    }
+
+    // the source and offline mode are global, so everything relying on them
+    // is checked in this one test
+    #[test]
+    fn requests_go_through_the_source_unless_offline() {
+        let _global_state = lock_global_state();
+        set_source(FixtureSource::new(
+            ron::from_str(USERS).unwrap(),
+            ron::from_str(TWEETS).unwrap(),
+        ));
+        set_offline(false);
+        // 2018-12-28T00:00:00Z
+        let since = OffsetDateTime::from_unix_timestamp(1545955200).unwrap();
+
+        assert_eq!(
+            ids(get_users_tweets_since("yudapearl", &since).unwrap()),
+            vec![1078627643417780226, 1078627642667032576]
+        );
+        assert_eq!(
+            ids(get_conversation_by_tweet_id(1078627642667032576).unwrap()),
+            vec![1078627642667032576, 1078627643417780226]
+        );
+
+        set_offline(true);
+        assert_eq!(
+            get_users_tweets_since("yudapearl", &since),
+            Err(ServerError::Offline)
+        );
+        assert_eq!(
+            get_conversation_by_tweet_id(1078627642667032576),
+            Err(ServerError::Offline)
+        );
+        set_offline(false);
+    }
 }
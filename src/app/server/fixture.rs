@@ -0,0 +1,114 @@
+use serde_derive::Deserialize;
+use std::fs;
+use time::OffsetDateTime;
+use twitter_v2::{Tweet, User};
+
+use super::{ArchiveSource, ServerError};
+
+/// Serves a fixed set of users and tweets instead of fetching them, for tests
+/// and for trying the archiver without a server. Asking for anything it
+/// doesn't have fails like an unreadable response would.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FixtureSource {
+    pub users: Vec<User>,
+    pub tweets: Vec<Tweet>,
+}
+
+impl FixtureSource {
+    pub fn new(users: Vec<User>, tweets: Vec<Tweet>) -> Self {
+        Self { users, tweets }
+    }
+
+    /// Reads a RON file shaped like `(users: [...], tweets: [...])`.
+    pub fn from_file(path: &str) -> Self {
+        let fixture_ron = fs::read_to_string(path)
+            .unwrap_or_else(|error| panic!("Failed to read fixture file {path}: {error}"));
+        ron::from_str(&fixture_ron)
+            .unwrap_or_else(|error| panic!("Failed to parse fixture file {path}: {error}"))
+    }
+
+    fn user(&self, twitter_handle: &str) -> Result<&User, ServerError> {
+        self.users
+            .iter()
+            .find(|user| user.username.eq_ignore_ascii_case(twitter_handle))
+            .ok_or_else(|| ServerError::BadResponse(format!("no fixture for @{twitter_handle}")))
+    }
+
+    /// The user's tweets newest first, like the API sends them.
+    fn tweets_by(&self, twitter_handle: &str) -> Result<Vec<Tweet>, ServerError> {
+        let user_id = self.user(twitter_handle)?.id.as_u64();
+        let mut tweets: Vec<Tweet> = self
+            .tweets
+            .iter()
+            .filter(|tweet| tweet.author_id.map(|id| id.as_u64()) == Some(user_id))
+            .cloned()
+            .collect();
+        tweets.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(tweets)
+    }
+}
+
+impl ArchiveSource for FixtureSource {
+    fn user_by_id(&self, id: u64) -> Result<User, ServerError> {
+        self.users
+            .iter()
+            .find(|user| user.id.as_u64() == id)
+            .cloned()
+            .ok_or_else(|| ServerError::BadResponse(format!("no fixture for user of id {id}")))
+    }
+
+    fn user_by_twitter_handle(&self, twitter_handle: &str) -> Result<User, ServerError> {
+        self.user(twitter_handle).cloned()
+    }
+
+    fn users_tweets(&self, twitter_handle: &str) -> Result<Vec<Tweet>, ServerError> {
+        self.tweets_by(twitter_handle)
+    }
+
+    fn users_tweets_since(
+        &self,
+        twitter_handle: &str,
+        since: &OffsetDateTime,
+    ) -> Result<Vec<Tweet>, ServerError> {
+        Ok(self
+            .tweets_by(twitter_handle)?
+            .into_iter()
+            .filter(|tweet| {
+                tweet
+                    .created_at
+                    .map_or(false, |created_at| created_at > *since)
+            })
+            .collect())
+    }
+
+    fn has_user_tweeted_since(
+        &self,
+        twitter_handle: &str,
+        since: &OffsetDateTime,
+    ) -> Result<bool, ServerError> {
+        Ok(!self.users_tweets_since(twitter_handle, since)?.is_empty())
+    }
+
+    fn conversation(&self, id: i64) -> Result<Vec<Tweet>, ServerError> {
+        Ok(self
+            .tweets
+            .iter()
+            .filter(|tweet| {
+                tweet
+                    .conversation_id
+                    .map(|conversation_id| conversation_id.as_u64())
+                    == Some(id as u64)
+            })
+            .cloned()
+            .collect())
+    }
+
+    fn tweet_by_id(&self, id: i64) -> Result<Option<Tweet>, ServerError> {
+        Ok(self
+            .tweets
+            .iter()
+            .find(|tweet| tweet.id.as_u64() == id as u64)
+            .cloned())
+    }
+}
@@ -0,0 +1,77 @@
+use reqwest;
+use ron;
+use serde::de::DeserializeOwned;
+use time::OffsetDateTime;
+use twitter_v2::{Tweet, User};
+
+use super::{ArchiveSource, ServerError};
+use crate::utils;
+
+/// The archiver's own API, which answers in Rusty Object Notation.
+#[derive(Debug, Clone)]
+pub struct RonApiSource {
+    base_url: String,
+}
+
+impl RonApiSource {
+    /// `base_url` ends with a slash, like `API_PROD`.
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+        }
+    }
+
+    fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ServerError> {
+        let response = reqwest::blocking::get(format!("{}{path}", self.base_url))
+            .and_then(|response| response.text())
+            .map_err(|error| ServerError::Unreachable(error.to_string()))?;
+        ron::from_str(&response)
+            .map_err(|error| ServerError::BadResponse(format!("{path}: {error}")))
+    }
+}
+
+impl ArchiveSource for RonApiSource {
+    fn user_by_id(&self, id: u64) -> Result<User, ServerError> {
+        self.get(&format!("userbyid/{id}"))
+    }
+
+    fn user_by_twitter_handle(&self, twitter_handle: &str) -> Result<User, ServerError> {
+        self.get(&format!("user/{twitter_handle}/info"))
+    }
+
+    fn users_tweets(&self, twitter_handle: &str) -> Result<Vec<Tweet>, ServerError> {
+        self.get(&format!("user/{twitter_handle}/tweets"))
+    }
+
+    fn users_tweets_since(
+        &self,
+        twitter_handle: &str,
+        since: &OffsetDateTime,
+    ) -> Result<Vec<Tweet>, ServerError> {
+        let since = utils::convert_date_to_chrono(Some(since.clone()));
+        let rfc3339_datestring = since.to_rfc3339();
+        self.get(&format!(
+            "user/{twitter_handle}/tweets-since/{rfc3339_datestring}"
+        ))
+    }
+
+    fn has_user_tweeted_since(
+        &self,
+        twitter_handle: &str,
+        since: &OffsetDateTime,
+    ) -> Result<bool, ServerError> {
+        let since = utils::convert_date_to_chrono(Some(since.clone()));
+        let rfc3339_datestring = since.to_rfc3339();
+        self.get(&format!(
+            "user/{twitter_handle}/has_tweeted_since/{rfc3339_datestring}"
+        ))
+    }
+
+    fn conversation(&self, id: i64) -> Result<Vec<Tweet>, ServerError> {
+        self.get(&format!("conversation/{id}"))
+    }
+
+    fn tweet_by_id(&self, id: i64) -> Result<Option<Tweet>, ServerError> {
+        self.get(&format!("tweet/{id}"))
+    }
+}
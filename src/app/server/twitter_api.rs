@@ -0,0 +1,649 @@
+use chrono::{SecondsFormat, Utc};
+use reqwest::blocking::{Client, Response};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde_derive::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use time::OffsetDateTime;
+use twitter_v2::{Tweet, User};
+
+use super::{is_offline, ArchiveSource, ServerError};
+use crate::utils;
+
+pub const TWITTER_API_URL: &str = "https://api.twitter.com/2/";
+
+const TWEET_FIELDS: &str = "attachments,author_id,context_annotations,conversation_id,created_at,\
+    entities,geo,id,in_reply_to_user_id,lang,possibly_sensitive,public_metrics,referenced_tweets,\
+    reply_settings,source,text,withheld";
+const USER_FIELDS: &str = "created_at,description,entities,id,location,name,pinned_tweet_id,\
+    profile_image_url,protected,public_metrics,url,username,verified,withheld";
+// the authors of tweets and the tweets they reference come along with them,
+// which saves asking for each on its own when they're archived
+const TWEET_EXPANSIONS: &str =
+    "author_id,in_reply_to_user_id,referenced_tweets.id,referenced_tweets.id.author_id";
+const MAX_RESULTS_PER_PAGE: &str = "100";
+// when a rate limited response doesn't say when the limit resets, wait out a
+// whole window, which is 15 minutes for every endpoint used here
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(15 * 60);
+// a request still refused after this many waits for the limit to reset is
+// given up on, as is one refused without saying when to try again
+const MAX_RATE_LIMITED_RETRIES: usize = 3;
+// how often a wait for a rate limit checks whether the archive went offline
+const RATE_LIMIT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Calls the Twitter API v2 directly with an app's bearer token.
+///
+/// Every response is paged through to the end and the rate limit headers are
+/// respected by waiting for the limit to reset, which can take up to 15
+/// minutes, unless the archive goes offline meanwhile. A request that stays
+/// rate limited fails with `ServerError::RateLimited`. Users and tweets that come along through expansions are kept, so
+/// asking for them later doesn't cost a request.
+///
+/// Requests are made here rather than through `twitter_v2::TwitterApi`, whose
+/// base URL is fixed to api.twitter.com so it can't be pointed at a mock
+/// server, and which is async while sources answer blocking calls. That's
+/// also why the JSON responses are parsed here with `serde_json`, into the
+/// same `twitter_v2` types.
+pub struct TwitterApiSource {
+    base_url: String,
+    bearer_token: String,
+    client: Client,
+    /// Twitter limits each endpoint on its own, so a run out limit only holds
+    /// back requests to the same endpoint until it resets
+    rate_limited_until: Mutex<HashMap<&'static str, SystemTime>>,
+    users: Mutex<HashMap<u64, User>>,
+    tweets: Mutex<HashMap<u64, Tweet>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Payload<T> {
+    data: Option<T>,
+    includes: Option<Includes>,
+    meta: Option<Meta>,
+    errors: Option<Vec<PayloadError>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Includes {
+    users: Vec<User>,
+    tweets: Vec<Tweet>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Meta {
+    next_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PayloadError {
+    title: String,
+    detail: Option<String>,
+}
+
+impl<T> Payload<T> {
+    fn error_message(&self) -> String {
+        match self.errors.iter().flatten().next() {
+            Some(error) => match &error.detail {
+                Some(detail) => format!("{}: {}", error.title, detail),
+                None => error.title.clone(),
+            },
+            None => "no data in the response".to_string(),
+        }
+    }
+}
+
+impl TwitterApiSource {
+    /// `base_url` ends with a slash, like `TWITTER_API_URL`, and can point at a
+    /// mock server instead.
+    pub fn new(base_url: &str, bearer_token: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            bearer_token: bearer_token.to_string(),
+            client: Client::new(),
+            rate_limited_until: Mutex::new(HashMap::new()),
+            users: Mutex::new(HashMap::new()),
+            tweets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Requests `path`, which `endpoint` names without its ids for keeping
+    /// track of rate limits, like `users/:id/tweets`.
+    fn get<T: DeserializeOwned>(
+        &self,
+        endpoint: &'static str,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<Payload<T>, ServerError> {
+        let mut rate_limited_retries = 0;
+        loop {
+            self.wait_for_rate_limit(endpoint)?;
+            let response = self
+                .client
+                .get(format!("{}{path}", self.base_url))
+                .bearer_auth(&self.bearer_token)
+                .query(query)
+                .send()
+                .map_err(|error| ServerError::Unreachable(error.to_string()))?;
+
+            let rate_limit_reset = header_value(&response, "x-rate-limit-reset")
+                .map(|reset| UNIX_EPOCH + Duration::from_secs(reset));
+            if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                self.rate_limit(
+                    endpoint,
+                    rate_limit_reset.unwrap_or_else(|| SystemTime::now() + RATE_LIMIT_WINDOW),
+                );
+                let is_retryable = rate_limit_reset.is_some()
+                    && rate_limited_retries < MAX_RATE_LIMITED_RETRIES;
+                if !is_retryable {
+                    return Err(ServerError::RateLimited(format!(
+                        "{path} answered {} after {rate_limited_retries} retries",
+                        response.status()
+                    )));
+                }
+                rate_limited_retries += 1;
+                continue;
+            }
+            if let (Some(0), Some(reset)) = (
+                header_value(&response, "x-rate-limit-remaining"),
+                rate_limit_reset,
+            ) {
+                self.rate_limit(endpoint, reset);
+            }
+
+            let status = response.status();
+            let body = response
+                .text()
+                .map_err(|error| ServerError::Unreachable(error.to_string()))?;
+            if !status.is_success() {
+                return Err(ServerError::BadResponse(format!(
+                    "{path} answered {status}: {body}"
+                )));
+            }
+            let payload: Payload<T> = serde_json::from_str(&body)
+                .map_err(|error| ServerError::BadResponse(format!("{path}: {error}")))?;
+            self.keep_includes(&payload.includes);
+            return Ok(payload);
+        }
+    }
+
+    /// Every tweet across all the pages of a response. Timelines take their
+    /// page token as `pagination_token` and searches as `next_token`.
+    fn get_all_tweets<'a>(
+        &self,
+        endpoint: &'static str,
+        path: &str,
+        query: Vec<(&'a str, String)>,
+        page_token_name: &'a str,
+    ) -> Result<Vec<Tweet>, ServerError> {
+        let mut tweets = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            let mut page_query = query.clone();
+            if let Some(next_token) = &next_token {
+                page_query.push((page_token_name, next_token.clone()));
+            }
+            let payload: Payload<Vec<Tweet>> = self.get(endpoint, path, &page_query)?;
+            tweets.extend(payload.data.unwrap_or_default());
+            next_token = payload.meta.and_then(|meta| meta.next_token);
+            if next_token.is_none() {
+                return Ok(tweets);
+            }
+        }
+    }
+
+    /// Waits until the endpoint's rate limit resets, or fails as soon as the
+    /// archive goes offline so a long wait doesn't hold up going offline.
+    fn wait_for_rate_limit(&self, endpoint: &'static str) -> Result<(), ServerError> {
+        let rate_limited_until = self
+            .rate_limited_until
+            .lock()
+            .expect("Failed to lock the rate limits")
+            .get(endpoint)
+            .cloned();
+        if let Some(rate_limited_until) = rate_limited_until {
+            // a second more so the window has surely reset
+            let resets_at = rate_limited_until + Duration::from_secs(1);
+            if let Ok(wait) = resets_at.duration_since(SystemTime::now()) {
+                eprintln!(
+                    "Rate limited by the Twitter API on {endpoint}, waiting {} seconds",
+                    wait.as_secs()
+                );
+                while SystemTime::now() < resets_at {
+                    if is_offline() {
+                        return Err(ServerError::Offline);
+                    }
+                    thread::sleep(RATE_LIMIT_POLL_INTERVAL);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn rate_limit(&self, endpoint: &'static str, until: SystemTime) {
+        self.rate_limited_until
+            .lock()
+            .expect("Failed to lock the rate limits")
+            .insert(endpoint, until);
+    }
+
+    fn keep_includes(&self, includes: &Option<Includes>) {
+        if let Some(includes) = includes {
+            let mut users = self.users.lock().expect("Failed to lock the known users");
+            for user in &includes.users {
+                users.insert(user.id.as_u64(), user.clone());
+            }
+            let mut tweets = self.tweets.lock().expect("Failed to lock the known tweets");
+            for tweet in &includes.tweets {
+                tweets.insert(tweet.id.as_u64(), tweet.clone());
+            }
+        }
+    }
+
+    fn keep_user(&self, user: &User) {
+        self.users
+            .lock()
+            .expect("Failed to lock the known users")
+            .insert(user.id.as_u64(), user.clone());
+    }
+
+    fn user_id(&self, twitter_handle: &str) -> Result<u64, ServerError> {
+        Ok(self.user_by_twitter_handle(twitter_handle)?.id.as_u64())
+    }
+}
+
+impl ArchiveSource for TwitterApiSource {
+    fn user_by_id(&self, id: u64) -> Result<User, ServerError> {
+        if let Some(user) = self
+            .users
+            .lock()
+            .expect("Failed to lock the known users")
+            .get(&id)
+        {
+            return Ok(user.clone());
+        }
+        let payload: Payload<User> =
+            self.get("users/:id", &format!("users/{id}"), &user_query())?;
+        match &payload.data {
+            Some(user) => {
+                self.keep_user(user);
+                Ok(user.clone())
+            }
+            None => Err(ServerError::BadResponse(payload.error_message())),
+        }
+    }
+
+    fn user_by_twitter_handle(&self, twitter_handle: &str) -> Result<User, ServerError> {
+        let known_user = self
+            .users
+            .lock()
+            .expect("Failed to lock the known users")
+            .values()
+            .find(|user| user.username.eq_ignore_ascii_case(twitter_handle))
+            .cloned();
+        if let Some(user) = known_user {
+            return Ok(user);
+        }
+        let payload: Payload<User> = self.get(
+            "users/by/username/:username",
+            &format!("users/by/username/{twitter_handle}"),
+            &user_query(),
+        )?;
+        match &payload.data {
+            Some(user) => {
+                self.keep_user(user);
+                Ok(user.clone())
+            }
+            None => Err(ServerError::BadResponse(payload.error_message())),
+        }
+    }
+
+    fn users_tweets(&self, twitter_handle: &str) -> Result<Vec<Tweet>, ServerError> {
+        let user_id = self.user_id(twitter_handle)?;
+        let mut query = tweet_query();
+        query.push(("max_results", MAX_RESULTS_PER_PAGE.to_string()));
+        self.get_all_tweets(
+            "users/:id/tweets",
+            &format!("users/{user_id}/tweets"),
+            query,
+            "pagination_token",
+        )
+    }
+
+    fn users_tweets_since(
+        &self,
+        twitter_handle: &str,
+        since: &OffsetDateTime,
+    ) -> Result<Vec<Tweet>, ServerError> {
+        let user_id = self.user_id(twitter_handle)?;
+        let mut query = tweet_query();
+        query.push(("max_results", MAX_RESULTS_PER_PAGE.to_string()));
+        query.push(("start_time", api_datetime(since)));
+        // start_time includes tweets from the very second given, like the
+        // latest archived one
+        Ok(self
+            .get_all_tweets(
+                "users/:id/tweets",
+                &format!("users/{user_id}/tweets"),
+                query,
+                "pagination_token",
+            )?
+            .into_iter()
+            .filter(|tweet| {
+                tweet
+                    .created_at
+                    .map_or(false, |created_at| created_at > *since)
+            })
+            .collect())
+    }
+
+    fn has_user_tweeted_since(
+        &self,
+        twitter_handle: &str,
+        since: &OffsetDateTime,
+    ) -> Result<bool, ServerError> {
+        let user_id = self.user_id(twitter_handle)?;
+        // one small page is enough to tell, 5 is the fewest the API returns
+        let query = vec![
+            ("tweet.fields", "created_at".to_string()),
+            ("max_results", "5".to_string()),
+            ("start_time", api_datetime(since)),
+        ];
+        let payload: Payload<Vec<Tweet>> =
+            self.get("users/:id/tweets", &format!("users/{user_id}/tweets"), &query)?;
+        Ok(payload.data.unwrap_or_default().iter().any(|tweet| {
+            tweet
+                .created_at
+                .map_or(false, |created_at| created_at > *since)
+        }))
+    }
+
+    /// The conversation's first tweet and its replies. Recent search only
+    /// reaches back a week, so older replies are out of reach.
+    fn conversation(&self, id: i64) -> Result<Vec<Tweet>, ServerError> {
+        let mut query = tweet_query();
+        query.push(("query", format!("conversation_id:{id}")));
+        query.push(("max_results", MAX_RESULTS_PER_PAGE.to_string()));
+        let mut conversation = self.get_all_tweets(
+            "tweets/search/recent",
+            "tweets/search/recent",
+            query,
+            "next_token",
+        )?;
+        let has_first_tweet = conversation
+            .iter()
+            .any(|tweet| tweet.id.as_u64() == id as u64);
+        if !has_first_tweet {
+            if let Some(first_tweet) = self.tweet_by_id(id)? {
+                conversation.insert(0, first_tweet);
+            }
+        }
+        Ok(conversation)
+    }
+
+    fn tweet_by_id(&self, id: i64) -> Result<Option<Tweet>, ServerError> {
+        let known_tweet = self
+            .tweets
+            .lock()
+            .expect("Failed to lock the known tweets")
+            .get(&(id as u64))
+            .cloned();
+        if known_tweet.is_some() {
+            return Ok(known_tweet);
+        }
+        // a deleted or protected tweet comes back as an error instead of data
+        let payload: Payload<Tweet> =
+            self.get("tweets/:id", &format!("tweets/{id}"), &tweet_query())?;
+        Ok(payload.data)
+    }
+}
+
+fn tweet_query() -> Vec<(&'static str, String)> {
+    vec![
+        ("tweet.fields", TWEET_FIELDS.to_string()),
+        ("user.fields", USER_FIELDS.to_string()),
+        ("expansions", TWEET_EXPANSIONS.to_string()),
+    ]
+}
+
+fn user_query() -> Vec<(&'static str, String)> {
+    vec![("user.fields", USER_FIELDS.to_string())]
+}
+
+/// Formats a time the way the API's `start_time` expects, like `2020-01-01T00:00:00Z`.
+fn api_datetime(datetime: &OffsetDateTime) -> String {
+    utils::convert_date_to_chrono(Some(*datetime))
+        .with_timezone(&Utc)
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn header_value(response: &Response, name: &str) -> Option<u64> {
+    response.headers().get(name)?.to_str().ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::lock_global_state;
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    struct MockResponse {
+        status: u16,
+        headers: Vec<(&'static str, String)>,
+        body: &'static str,
+    }
+
+    fn ok(body: &'static str) -> MockResponse {
+        MockResponse {
+            status: 200,
+            headers: Vec::new(),
+            body,
+        }
+    }
+
+    /// Serves `responses` in order, one per request, on a local port and
+    /// records each request's path and query. Requests past the last response
+    /// find nothing listening.
+    fn mock_server(responses: Vec<MockResponse>) -> (TwitterApiSource, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded_requests = requests.clone();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                // the rest of the request is headers, up to a blank line
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+                let target = request_line.split(' ').nth(1).unwrap_or_default();
+                recorded_requests.lock().unwrap().push(target.to_string());
+
+                let headers: String = response
+                    .headers
+                    .iter()
+                    .map(|(name, value)| format!("{name}: {value}\r\n"))
+                    .collect();
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n{headers}\r\n{}",
+                    response.status,
+                    response.body.len(),
+                    response.body
+                )
+                .unwrap();
+            }
+        });
+        (TwitterApiSource::new(&base_url, "token"), requests)
+    }
+
+    fn now_in_seconds() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+    }
+
+    fn ids(tweets: &Vec<Tweet>) -> Vec<u64> {
+        tweets.iter().map(|tweet| tweet.id.as_u64()).collect()
+    }
+
+    const USER: &str = r#"{"data": {"id": "1", "name": "Judea Pearl", "username": "yudapearl"}}"#;
+
+    #[test]
+    fn timelines_are_paged_with_pagination_token() {
+        let (source, requests) = mock_server(vec![
+            ok(USER),
+            ok(r#"{"data": [{"id": "12", "text": "2/2"}], "meta": {"next_token": "page2"}}"#),
+            ok(r#"{"data": [{"id": "11", "text": "1/2"}], "meta": {}}"#),
+        ]);
+
+        let tweets = source.users_tweets("yudapearl").unwrap();
+
+        assert_eq!(ids(&tweets), vec![12, 11]);
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[1].starts_with("/users/1/tweets?"));
+        assert!(!requests[1].contains("pagination_token"));
+        assert!(requests[2].contains("pagination_token=page2"));
+    }
+
+    #[test]
+    fn searches_are_paged_with_next_token() {
+        let (source, requests) = mock_server(vec![
+            ok(r#"{"data": [{"id": "12", "text": "2/2"}], "meta": {"next_token": "page2"}}"#),
+            ok(r#"{"data": [{"id": "11", "text": "1/2"}]}"#),
+        ]);
+
+        let conversation = source.conversation(11).unwrap();
+
+        assert_eq!(ids(&conversation), vec![12, 11]);
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].starts_with("/tweets/search/recent?"));
+        assert!(requests[1].contains("next_token=page2"));
+    }
+
+    #[test]
+    fn expansions_are_kept_for_later_lookups() {
+        let (source, requests) = mock_server(vec![ok(r#"{
+            "data": {"id": "12", "text": "2/2", "author_id": "1"},
+            "includes": {
+                "users": [{"id": "1", "name": "Judea Pearl", "username": "yudapearl"}],
+                "tweets": [{"id": "11", "text": "1/2", "author_id": "1"}]
+            }
+        }"#)]);
+
+        source.tweet_by_id(12).unwrap();
+        let user = source.user_by_id(1).unwrap();
+        let tweet = source.tweet_by_id(11).unwrap();
+
+        assert_eq!(user.username, "yudapearl");
+        assert_eq!(tweet.map(|tweet| tweet.id.as_u64()), Some(11));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rate_limited_requests_are_retried_after_the_reset() {
+        // waiting for the reset gives up if another test turns offline mode on
+        let _global_state = lock_global_state();
+        let (source, requests) = mock_server(vec![
+            MockResponse {
+                status: 429,
+                headers: vec![("x-rate-limit-reset", now_in_seconds().to_string())],
+                body: r#"{"title": "Too Many Requests"}"#,
+            },
+            ok(USER),
+        ]);
+
+        let user = source.user_by_id(1).unwrap();
+
+        assert_eq!(user.username, "yudapearl");
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn rate_limited_requests_without_a_reset_give_up() {
+        let (source, requests) = mock_server(vec![MockResponse {
+            status: 429,
+            headers: Vec::new(),
+            body: r#"{"title": "Too Many Requests"}"#,
+        }]);
+        let started_at = Instant::now();
+
+        let result = source.user_by_id(1);
+
+        assert!(matches!(result, Err(ServerError::RateLimited(_))));
+        assert!(started_at.elapsed() < Duration::from_secs(60));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rate_limited_requests_give_up_after_retrying() {
+        let _global_state = lock_global_state();
+        let rate_limited = || MockResponse {
+            status: 429,
+            headers: vec![("x-rate-limit-reset", now_in_seconds().to_string())],
+            body: r#"{"title": "Too Many Requests"}"#,
+        };
+        let (source, requests) = mock_server(
+            (0..=MAX_RATE_LIMITED_RETRIES)
+                .map(|_| rate_limited())
+                .collect(),
+        );
+
+        let result = source.user_by_id(1);
+
+        assert!(matches!(result, Err(ServerError::RateLimited(_))));
+        assert_eq!(
+            requests.lock().unwrap().len(),
+            MAX_RATE_LIMITED_RETRIES + 1
+        );
+    }
+
+    #[test]
+    fn rate_limits_only_hold_back_their_own_endpoint() {
+        let (source, requests) = mock_server(vec![
+            MockResponse {
+                status: 200,
+                headers: vec![
+                    ("x-rate-limit-remaining", "0".to_string()),
+                    ("x-rate-limit-reset", (now_in_seconds() + 600).to_string()),
+                ],
+                body: USER,
+            },
+            ok(r#"{"data": {"id": "11", "text": "1/2"}}"#),
+        ]);
+        let started_at = Instant::now();
+
+        source.user_by_id(1).unwrap();
+        source.tweet_by_id(11).unwrap();
+
+        assert!(started_at.elapsed() < Duration::from_secs(60));
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn error_statuses_are_bad_responses() {
+        let (source, _) = mock_server(vec![MockResponse {
+            status: 401,
+            headers: Vec::new(),
+            body: r#"{"title": "Unauthorized"}"#,
+        }]);
+
+        let result = source.tweet_by_id(11);
+
+        assert!(matches!(
+            result,
+            Err(ServerError::BadResponse(error)) if error.contains("401")
+        ));
+    }
+}
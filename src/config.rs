@@ -0,0 +1,162 @@
+use crate::app::server::{twitter_api::TWITTER_API_URL, API_PROD};
+use serde_derive::Deserialize;
+use std::{env, fs};
+
+const DEFAULT_CONFIG_FILE: &str = "config.ron";
+const DEFAULT_DATABASE_URL: &str = "sqlite:./tweets.db?mode=rwc";
+
+/// Startup settings for the archiver.
+///
+/// Each setting is resolved in order from the built in defaults, the RON
+/// config file, environment variables and finally command line flags, with
+/// later sources overriding earlier ones.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    pub database_url: String,
+    /// Where tweets and users are fetched from.
+    pub source: SourceKind,
+    /// The RON API tweets and users are fetched from.
+    pub api_url: String,
+    pub twitter_api_url: String,
+    /// The app bearer token the Twitter API source authenticates with. Best
+    /// kept in `TWITTER_BEARER_TOKEN` rather than the config file.
+    pub twitter_bearer_token: Option<String>,
+    /// A RON file of users and tweets to serve instead of fetching from
+    /// `api_url`, see `FixtureSource`.
+    pub fixture_file: Option<String>,
+    /// Whether to check the tracked accounts for new tweets once the archive
+    /// is on screen. Turned off with `--no-sync` or `ARCHIVER_SYNC=false`.
+    pub sync_on_startup: bool,
+    /// Start in offline mode, reading only from the database. Set with
+    /// `--offline` or `ARCHIVER_OFFLINE=true`.
+    pub offline: bool,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            database_url: DEFAULT_DATABASE_URL.to_string(),
+            source: SourceKind::RonApi,
+            api_url: API_PROD.to_string(),
+            twitter_api_url: TWITTER_API_URL.to_string(),
+            twitter_bearer_token: None,
+            fixture_file: None,
+            sync_on_startup: true,
+            offline: false,
+        }
+    }
+}
+
+impl ArchiveConfig {
+    pub fn load() -> Self {
+        let args: Vec<String> = env::args().skip(1).collect();
+
+        let config_file = flag_value(&args, "--config")
+            .or_else(|| env::var("ARCHIVER_CONFIG").ok())
+            .unwrap_or_else(|| DEFAULT_CONFIG_FILE.to_string());
+        let mut config = Self::from_file(&config_file);
+
+        if let Ok(database_url) = env::var("DATABASE_URL") {
+            config.database_url = database_url;
+        }
+
+        if let Ok(source) = env::var("ARCHIVER_SOURCE") {
+            config.source = SourceKind::parse(&source);
+        }
+
+        if let Ok(twitter_bearer_token) = env::var("TWITTER_BEARER_TOKEN") {
+            config.twitter_bearer_token = Some(twitter_bearer_token);
+        }
+
+        if let Ok(twitter_api_url) = env::var("TWITTER_API_URL") {
+            config.twitter_api_url = twitter_api_url;
+        }
+
+        if let Ok(api_url) = env::var("ARCHIVER_API_URL") {
+            config.api_url = api_url;
+        }
+
+        if let Ok(fixture_file) = env::var("ARCHIVER_FIXTURE") {
+            config.fixture_file = Some(fixture_file);
+        }
+
+        if let Ok(sync) = env::var("ARCHIVER_SYNC") {
+            config.sync_on_startup = !matches!(sync.as_str(), "false" | "0");
+        }
+
+        if let Ok(offline) = env::var("ARCHIVER_OFFLINE") {
+            config.offline = matches!(offline.as_str(), "true" | "1");
+        }
+
+        if let Some(database_url) = flag_value(&args, "--database-url") {
+            config.database_url = database_url;
+        }
+
+        if let Some(source) = flag_value(&args, "--source") {
+            config.source = SourceKind::parse(&source);
+        }
+
+        if let Some(twitter_api_url) = flag_value(&args, "--twitter-api-url") {
+            config.twitter_api_url = twitter_api_url;
+        }
+
+        if let Some(api_url) = flag_value(&args, "--api-url") {
+            config.api_url = api_url;
+        }
+
+        if let Some(fixture_file) = flag_value(&args, "--fixture") {
+            config.fixture_file = Some(fixture_file);
+        }
+
+        if args.iter().any(|arg| arg == "--no-sync") {
+            config.sync_on_startup = false;
+        }
+
+        if args.iter().any(|arg| arg == "--offline") {
+            config.offline = true;
+        }
+
+        config
+    }
+
+    fn from_file(path: &str) -> Self {
+        match fs::read_to_string(path) {
+            Ok(config_ron) => ron::from_str(&config_ron)
+                .unwrap_or_else(|error| panic!("Failed to parse config file {path}: {error}")),
+            Err(_) => Self::default(),
+        }
+    }
+}
+
+/// The kinds of `ArchiveSource` the archiver can fetch from, set in the config
+/// file as `source: TwitterApi` or with `--source twitter-api`. A fixture file
+/// overrides either.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum SourceKind {
+    RonApi,
+    TwitterApi,
+}
+
+impl SourceKind {
+    fn parse(source: &str) -> Self {
+        match source.to_lowercase().as_str() {
+            "ron-api" | "ronapi" => SourceKind::RonApi,
+            "twitter-api" | "twitterapi" => SourceKind::TwitterApi,
+            _ => panic!("Unknown source {source}, expected ron-api or twitter-api"),
+        }
+    }
+}
+
+/// Reads the value of a `--flag value` or `--flag=value` style argument.
+fn flag_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == flag {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix(flag)
+                .and_then(|rest| rest.strip_prefix('='))
+                .map(|value| value.to_string())
+        }
+    })
+}
@@ -1,30 +1,76 @@
 use app::data::setup;
+use app::server::{FixtureSource, RonApiSource, TwitterApiSource};
+use config::{ArchiveConfig, SourceKind};
+use search::{SearchOptions, SearchOrder, SearchQuery};
 use iced::futures::executor::block_on;
 use iced::pure::widget::{Button, Column, Row, Text, TextInput};
 use iced::pure::{
     button, column, container, row, scrollable, text, text_input, Application, Element, Widget,
 };
-use iced::{alignment, executor, Alignment, Color, Command, Length, Settings};
+use iced::{alignment, clipboard, executor, Alignment, Color, Command, Length, Settings};
 use sea_orm::DatabaseConnection;
 use twitter_v2::oauth2::url::quirks::search;
 use twitter_v2::{Tweet, User};
-use utils::{SelectList, TweetData, UserData};
+use std::collections::HashSet;
+use std::future::Future;
+use utils::{
+    tweet_text_lines, ConversationNode, ConversationTree, SearchResult, SelectList, ThreadFormat,
+    TrackedAccountData, TweetData, TweetReferencesData, TweetTextSegment, UnrolledThread,
+    UserSnapshotData,
+};
 
 pub mod app;
+pub mod config;
+pub mod search;
 pub mod style;
 pub mod theme;
 pub mod utils;
 pub mod seed;
-const USER_TWITTER_HANDLE: &str = "yudapearl";
 
 pub fn main() -> iced::Result {
-    App::run(Settings::default())
+    let archive_config = ArchiveConfig::load();
+    let db = block_on(setup::set_up_db(&archive_config.database_url)).unwrap_or_else(|error| {
+        eprintln!("Failed to set up database: {}", error);
+        std::process::exit(1)
+    });
+    set_up_source(&archive_config);
+    app::server::set_offline(archive_config.offline);
+    App::run(Settings::with_flags((db, archive_config)))
+}
+
+fn set_up_source(archive_config: &ArchiveConfig) {
+    if let Some(fixture_file) = &archive_config.fixture_file {
+        app::server::set_source(FixtureSource::from_file(fixture_file));
+        return;
+    }
+    match archive_config.source {
+        SourceKind::RonApi => app::server::set_source(RonApiSource::new(&archive_config.api_url)),
+        SourceKind::TwitterApi => {
+            let bearer_token = archive_config
+                .twitter_bearer_token
+                .as_ref()
+                .unwrap_or_else(|| {
+                    eprintln!("The Twitter API source needs TWITTER_BEARER_TOKEN to be set");
+                    std::process::exit(1)
+                });
+            app::server::set_source(TwitterApiSource::new(
+                &archive_config.twitter_api_url,
+                bearer_token,
+            ));
+        }
+    }
 }
 #[derive(Debug, Clone)]
 struct App {
     model: SelectList<Snapshot>,
     config: Config,
     search_input: String,
+    search_error: Option<String>,
+    open_url_error: Option<String>,
+    track_input: String,
+    /// What is running in the background, like seeding, while pages stay usable
+    background_task: Option<String>,
+    next_load_id: usize,
     data: DatabaseConnection,
 }
 
@@ -36,50 +82,96 @@ struct Config {
 #[derive(Debug, Clone)]
 
 enum Message {
-    DisplayTweet(TweetData), //you might want to get rid of this, or change display conversation to this
+    /// A page opened by `App::load` has finished loading
+    Loaded(usize, Snapshot),
+    DisplayTweet(TweetData),
     DisplayUsersTweets(User),
+    DisplayUserByTwitterHandle(String),
+    OpenUrl(String),
     DisplayConversation(TweetData),
+    ToggleReplies(u64),
+    ToggleUnrolledThread,
+    CopyThread(ThreadFormat),
+    ExportThread(ThreadFormat),
+    DisplayProfileHistory(User),
     Home,
     Back,
     Forward,
     ViewMoreTweets,
     SearchInputChanged(String),
     Search(String),
+    SortSearch(SearchOrder),
+    FuzzySearch(bool),
     SeedConversations,
+    TrackInputChanged(String),
+    TrackAccount,
+    UntrackAccount(i64),
+    TrackedAccountsChanged(Vec<TrackedAccountData>),
+    /// Tracking an account finished, with the accounts now tracked
+    AccountTracked(Vec<TrackedAccountData>),
+    /// The background check for new tweets finished, with the users it found some for
+    NewTweetsArchived(Vec<User>),
+    UsersTweetsChanged(User, Vec<TweetData>),
+    BackgroundTaskFinished,
+    ToggleOffline,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 enum Snapshot {
-    TweetView(TweetData),
+    /// A page still loading, with the id its result comes back under and a
+    /// description of what is being loaded
+    LoadingView(usize, String),
+    HomeView(Vec<TrackedAccountData>),
+    TweetView(TweetData, TweetReferencesData),
     UserView(User, Vec<TweetData>),
-    ConversationView(Vec<TweetData>),
-    SearchView(String, Vec<TweetData>),
+    /// The conversation, the id of the tweet it was opened from, the ids of
+    /// the tweets whose replies are collapsed and whether the opened tweet's
+    /// self-thread is shown unrolled instead of the whole conversation
+    ConversationView(ConversationTree, u64, HashSet<u64>, bool),
+    /// The search input, how it was run, the results and "did you mean"
+    /// suggestions offered when there are none
+    SearchView(String, SearchOptions, Vec<SearchResult>, Vec<String>),
+    ProfileHistoryView(User, Vec<UserSnapshotData>),
+    /// Something that isn't archived and couldn't be fetched, with why
+    MissingView(String),
 }
 
 impl Application for App {
     type Message = Message;
     type Executor = executor::Default;
-    type Flags = ();
+    type Flags = (DatabaseConnection, ArchiveConfig);
 
-    fn new(_flags: ()) -> (Self, Command<Message>) {
-        let db = block_on(setup::set_up_db()).expect("Failed to set up database");
-        (
-            Self {
-                model: SelectList::new(Snapshot::UserView(
-                    block_on(app::load_user_from_twitter_handle(&db, USER_TWITTER_HANDLE)),
-                    block_on(app::load_users_tweets_from_twitter_handle(
-                        &db,
-                        USER_TWITTER_HANDLE,
-                    )),
-                )),
-                search_input: "".to_string(),
-                config: Config {
-                    tweets_per_page: 100,
-                },
-                data: db,
+    fn new(
+        (db, archive_config): (DatabaseConnection, ArchiveConfig),
+    ) -> (Self, Command<Message>) {
+        let mut app = Self {
+            model: SelectList::new(Snapshot::HomeView(Vec::new())),
+            search_input: "".to_string(),
+            search_error: None,
+            open_url_error: None,
+            track_input: "".to_string(),
+            background_task: None,
+            next_load_id: 0,
+            config: Config {
+                tweets_per_page: 100,
             },
-            Command::none(),
-        )
+            data: db.clone(),
+        };
+        // the archive goes on screen first, the server is only asked about new
+        // tweets once it's showing
+        let load_db = db.clone();
+        let command = app.reload("Loading tracked accounts".to_string(), async move {
+            Snapshot::HomeView(app::load_tracked_accounts(&load_db).await)
+        });
+        if !archive_config.sync_on_startup || app::server::is_offline() {
+            return (app, command);
+        }
+        app.background_task = Some("Checking for new tweets...".to_string());
+        let sync_command = Command::perform(
+            async move { app::sync_tracked_accounts(&db).await },
+            Message::NewTweetsArchived,
+        );
+        (app, Command::batch([command, sync_command]))
     }
 
     fn title(&self) -> String {
@@ -91,38 +183,111 @@ impl Application for App {
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
+        let db = self.data.clone();
         match message {
-            Message::DisplayTweet(tweet) => {
-                self.model.add(Snapshot::TweetView(tweet));
+            Message::Loaded(load_id, snapshot) => {
+                self.model.replace(
+                    |page| matches!(page, Snapshot::LoadingView(id, _) if *id == load_id),
+                    snapshot,
+                );
                 Command::none()
             }
+            Message::DisplayTweet(tweet) => self.load(
+                format!("Loading {}'s tweet", tweet.author_label()),
+                async move {
+                    let references = app::load_tweet_references(&db, &tweet).await;
+                    Snapshot::TweetView(tweet, references)
+                },
+            ),
             Message::DisplayUsersTweets(user) => {
-                let users_tweets = block_on(app::load_users_tweets_from_twitter_handle(
-                    &self.data,
-                    &user.username,
-                ));
-                self.model.add(Snapshot::UserView(user, users_tweets));
+                self.load(format!("Loading @{}'s tweets", user.username), async move {
+                    let users_tweets =
+                        app::load_users_tweets_from_twitter_handle(&db, &user.username).await;
+                    Snapshot::UserView(user, users_tweets)
+                })
+            }
+            Message::DisplayUserByTwitterHandle(twitter_handle) => {
+                self.load(format!("Loading @{}'s tweets", twitter_handle), async move {
+                    match app::load_user_from_twitter_handle(&db, &twitter_handle).await {
+                        Some(user) => {
+                            let users_tweets =
+                                app::load_users_tweets_from_twitter_handle(&db, &user.username)
+                                    .await;
+                            Snapshot::UserView(user, users_tweets)
+                        }
+                        None => Snapshot::MissingView(missing_from_archive(&format!(
+                            "@{}",
+                            twitter_handle
+                        ))),
+                    }
+                })
+            }
+            Message::OpenUrl(url) => {
+                self.open_url_error = app::open_url(&url).err();
+                if let Some(error) = &self.open_url_error {
+                    eprintln!("{}", error);
+                }
                 Command::none()
             }
             Message::DisplayConversation(tweet_data) => {
-                let conversation = block_on(app::load_conversation_from_tweet_id(
-                    &self.data,
-                    tweet_data
-                        .tweet
-                        .conversation_id
-                        .expect("bad conversation id")
-                        .as_u64()
-                        .try_into()
-                        .expect("Failed to parse u64 into i64"),
-                ));
-
-                self.model.add(Snapshot::ConversationView(conversation));
+                let conversation_id: i64 = tweet_data
+                    .tweet
+                    .conversation_id
+                    .expect("bad conversation id")
+                    .as_u64()
+                    .try_into()
+                    .expect("Failed to parse u64 into i64");
+                let tweet_id = tweet_data.tweet.id.as_u64();
+                self.load("Loading conversation".to_string(), async move {
+                    let conversation =
+                        app::load_conversation_from_tweet_id(&db, conversation_id).await;
+                    if conversation.is_empty() {
+                        return Snapshot::MissingView(missing_from_archive("This conversation"));
+                    }
+                    let conversation = ConversationTree::new(&conversation);
+                    // threads open unrolled, they're meant to be read in one go
+                    let is_unrolled = conversation.self_thread(tweet_id).is_some();
+                    Snapshot::ConversationView(conversation, tweet_id, HashSet::new(), is_unrolled)
+                })
+            }
+            Message::ToggleReplies(tweet_id) => {
+                if let Snapshot::ConversationView(_, _, collapsed_tweet_ids, _) =
+                    &mut self.model.selected
+                {
+                    if !collapsed_tweet_ids.remove(&tweet_id) {
+                        collapsed_tweet_ids.insert(tweet_id);
+                    }
+                }
                 Command::none()
             }
-            Message::Home => {
-                self.model.add(self.model.previous[0].clone());
+            Message::ToggleUnrolledThread => {
+                if let Snapshot::ConversationView(_, _, _, is_unrolled) = &mut self.model.selected {
+                    *is_unrolled = !*is_unrolled;
+                }
                 Command::none()
             }
+            Message::CopyThread(format) => match self.selected_thread() {
+                Some(thread) => clipboard::write(thread.render(format)),
+                None => Command::none(),
+            },
+            Message::ExportThread(format) => {
+                if let Some(thread) = self.selected_thread() {
+                    match thread.export(format) {
+                        Ok(file_name) => println!("Exported thread to {}", file_name),
+                        Err(e) => println!("Failed to export thread because {}", e),
+                    }
+                }
+                Command::none()
+            }
+            Message::DisplayProfileHistory(user) => {
+                self.load(format!("Loading @{}'s profile history", user.username), async move {
+                    let history = app::load_profile_history(&db, &user).await;
+                    Snapshot::ProfileHistoryView(user, history)
+                })
+            }
+            Message::Home => self.load("Loading tracked accounts".to_string(), async move {
+                Snapshot::HomeView(app::load_tracked_accounts(&db).await)
+            }),
             Message::Back => {
                 self.model.previous();
                 Command::none()
@@ -137,24 +302,131 @@ impl Application for App {
             }
             Message::SearchInputChanged(input) => {
                 self.search_input = input;
+                self.search_error = None;
                 Command::none()
             }
-            Message::Search(search_query) => {
-                let search = block_on(app::search_tweets_in_db(&self.data, &search_query));
-                self.model.add(Snapshot::SearchView(search_query, search));
+            Message::Search(search_query) => match SearchQuery::parse(&search_query) {
+                Ok(parsed_query) if parsed_query.is_empty() => Command::none(),
+                Ok(parsed_query) => self.load(
+                    format!("Searching for \"{}\"", search_query),
+                    load_search_view(db, search_query, parsed_query, SearchOptions::default()),
+                ),
+                Err(error) => {
+                    self.search_error = Some(error.to_string());
+                    Command::none()
+                }
+            },
+            Message::SortSearch(order) => match &self.model.selected {
+                Snapshot::SearchView(_, options, _, _) => {
+                    let options = SearchOptions { order, ..*options };
+                    self.rerun_search(options)
+                }
+                _ => Command::none(),
+            },
+            Message::FuzzySearch(is_fuzzy) => match &self.model.selected {
+                Snapshot::SearchView(_, options, _, _) => {
+                    let options = SearchOptions { is_fuzzy, ..*options };
+                    self.rerun_search(options)
+                }
+                _ => Command::none(),
+            },
+            Message::SeedConversations => {
+                self.background_task = Some("Seeding conversations...".to_string());
+                Command::perform(
+                    async move {
+                        for tracked_account in app::load_tracked_accounts(&db).await {
+                            let users_tweets = app::load_users_tweets_from_twitter_handle(
+                                &db,
+                                &tracked_account.user.username,
+                            )
+                            .await;
+                            println!(
+                                "seeding conversations for @{}...",
+                                tracked_account.user.username
+                            );
+                            app::seed_conversation_from_tweets(&db, &users_tweets).await;
+                        }
+                    },
+                    |_| Message::BackgroundTaskFinished,
+                )
+            }
+            Message::TrackInputChanged(input) => {
+                self.track_input = input;
                 Command::none()
             }
-            Message::SeedConversations => {
-                let users_tweets = block_on(app::load_users_tweets_from_twitter_handle(
-                    &self.data,
-                    "yudapearl",
-                ));
-                println!("seeding conversations...");
-                block_on(app::seed_conversation_from_tweets(
-                    &self.data,
-                    &users_tweets,
-                ));
-                //self.model.add(Snapshot::UserView(user, users_tweets));
+            Message::TrackAccount => {
+                let twitter_handle = self.track_input.trim().trim_start_matches('@').to_string();
+                // one background task at a time, like the Seed button
+                if twitter_handle.is_empty() || self.background_task.is_some() {
+                    return Command::none();
+                }
+                self.track_input = "".to_string();
+                self.background_task = Some(format!("Adding @{}...", twitter_handle));
+                Command::perform(
+                    async move {
+                        app::track_account(&db, &twitter_handle).await;
+                        app::load_tracked_accounts(&db).await
+                    },
+                    Message::AccountTracked,
+                )
+            }
+            Message::UntrackAccount(user_id) => Command::perform(
+                async move {
+                    app::untrack_account(&db, user_id).await;
+                    app::load_tracked_accounts(&db).await
+                },
+                Message::TrackedAccountsChanged,
+            ),
+            Message::TrackedAccountsChanged(tracked_accounts) => {
+                self.model.replace(
+                    |page| matches!(page, Snapshot::HomeView(_)),
+                    Snapshot::HomeView(tracked_accounts),
+                );
+                Command::none()
+            }
+            Message::AccountTracked(tracked_accounts) => {
+                self.background_task = None;
+                self.update(Message::TrackedAccountsChanged(tracked_accounts))
+            }
+            Message::NewTweetsArchived(users) => {
+                self.background_task = None;
+                // last synced times changed even for accounts without new tweets
+                let home_db = db.clone();
+                let mut commands = vec![Command::perform(
+                    async move { app::load_tracked_accounts(&home_db).await },
+                    Message::TrackedAccountsChanged,
+                )];
+                for user in users {
+                    let db = db.clone();
+                    commands.push(Command::perform(
+                        async move {
+                            let users_tweets =
+                                app::load_users_tweets_from_twitter_handle(&db, &user.username)
+                                    .await;
+                            (user, users_tweets)
+                        },
+                        |(user, users_tweets)| Message::UsersTweetsChanged(user, users_tweets),
+                    ));
+                }
+                Command::batch(commands)
+            }
+            Message::UsersTweetsChanged(user, users_tweets) => {
+                let user_id = user.id.as_u64();
+                self.model.replace(
+                    |page| match page {
+                        Snapshot::UserView(page_user, _) => page_user.id.as_u64() == user_id,
+                        _ => false,
+                    },
+                    Snapshot::UserView(user, users_tweets),
+                );
+                Command::none()
+            }
+            Message::BackgroundTaskFinished => {
+                self.background_task = None;
+                Command::none()
+            }
+            Message::ToggleOffline => {
+                app::server::set_offline(!app::server::is_offline());
                 Command::none()
             }
         }
@@ -163,12 +435,29 @@ impl Application for App {
     fn view(&self) -> Element<Message> {
         let present = &self.model.selected;
         let view_content = match present {
-            Snapshot::TweetView(tweet) => render_tweet_view(self, &tweet),
+            Snapshot::LoadingView(_, description) => render_loading_view(self, description),
+            Snapshot::HomeView(tracked_accounts) => render_home_view(self, tracked_accounts),
+            Snapshot::TweetView(tweet, references) => render_tweet_view(self, &tweet, references),
             Snapshot::UserView(user, tweets) => render_user_timeline_view(self, &user, &tweets),
-            Snapshot::ConversationView(tweet) => render_conversation_view(self, tweet),
-            Snapshot::SearchView(search_query, search_results) => {
-                render_search_view(self, search_query, search_results)
+            Snapshot::ConversationView(
+                conversation,
+                selected_tweet_id,
+                collapsed_tweet_ids,
+                is_unrolled,
+            ) => render_conversation_view(
+                self,
+                conversation,
+                selected_tweet_id,
+                collapsed_tweet_ids,
+                is_unrolled,
+            ),
+            Snapshot::SearchView(search_query, options, search_results, suggestions) => {
+                render_search_view(self, search_query, options, search_results, suggestions)
+            }
+            Snapshot::ProfileHistoryView(user, history) => {
+                render_profile_history_view(self, user, history)
             }
+            Snapshot::MissingView(description) => render_missing_view(self, description),
         };
         container(scrollable(
             column()
@@ -186,6 +475,112 @@ impl Application for App {
     }
 }
 
+impl App {
+    /// Opens a page that says what is loading until `snapshot` is ready. The
+    /// result replaces that page wherever it has been moved to in the history
+    /// by then, so navigating away in the meantime is fine.
+    fn load(
+        &mut self,
+        description: String,
+        snapshot: impl Future<Output = Snapshot> + Send + 'static,
+    ) -> Command<Message> {
+        let (loading_view, command) = self.perform_load(description, snapshot);
+        self.model.add(loading_view);
+        command
+    }
+
+    /// Like `load` but for the page already open, without adding to the history.
+    fn reload(
+        &mut self,
+        description: String,
+        snapshot: impl Future<Output = Snapshot> + Send + 'static,
+    ) -> Command<Message> {
+        let (loading_view, command) = self.perform_load(description, snapshot);
+        self.model.selected = loading_view;
+        command
+    }
+
+    fn perform_load(
+        &mut self,
+        description: String,
+        snapshot: impl Future<Output = Snapshot> + Send + 'static,
+    ) -> (Snapshot, Command<Message>) {
+        let load_id = self.next_load_id;
+        self.next_load_id += 1;
+        (
+            Snapshot::LoadingView(load_id, description),
+            Command::perform(snapshot, move |snapshot| Message::Loaded(load_id, snapshot)),
+        )
+    }
+
+    fn selected_thread(&self) -> Option<UnrolledThread> {
+        match &self.model.selected {
+            Snapshot::ConversationView(conversation, selected_tweet_id, _, _) => {
+                conversation.self_thread(*selected_tweet_id)
+            }
+            _ => None,
+        }
+    }
+
+    // changing how a search runs stays on the same page instead of adding to the history
+    fn rerun_search(&mut self, options: SearchOptions) -> Command<Message> {
+        if let Snapshot::SearchView(search_input, _, _, _) = &self.model.selected {
+            if let Ok(search_query) = SearchQuery::parse(search_input) {
+                let search_input = search_input.clone();
+                return self.reload(
+                    format!("Searching for \"{}\"", search_input),
+                    load_search_view(self.data.clone(), search_input, search_query, options),
+                );
+            }
+        }
+        Command::none()
+    }
+}
+
+async fn load_search_view(
+    db: DatabaseConnection,
+    search_input: String,
+    search_query: SearchQuery,
+    options: SearchOptions,
+) -> Snapshot {
+    let search_results = app::search_tweets_in_db(&db, &search_query, options).await;
+    // fuzzy matching already covers everything a respelling would find
+    let suggestions = if search_results.is_empty() && !options.is_fuzzy {
+        app::search_suggestions(&db, &search_input, &search_query).await
+    } else {
+        Vec::new()
+    };
+    Snapshot::SearchView(search_input, options, search_results, suggestions)
+}
+
+fn render_loading_view<'a>(app: &App, description: &str) -> Row<'a, Message> {
+    row().push(
+        column()
+            .push(
+                text(format!("{}...", description))
+                    .size(30)
+                    .width(Length::Fill)
+                    .horizontal_alignment(iced::alignment::Horizontal::Center),
+            )
+            .push(view_navigation(app))
+            .spacing(10),
+    )
+}
+
+fn render_home_view<'a>(app: &App, tracked_accounts: &Vec<TrackedAccountData>) -> Row<'a, Message> {
+    row().push(
+        column()
+            .push(view_home_title())
+            .push(view_navigation(app))
+            .push(view_track_account(
+                &app.track_input,
+                app.background_task.is_none(),
+            ))
+            .push(view_tracked_accounts(tracked_accounts))
+            .spacing(10),
+    )
+}
+
 fn render_user_timeline_view<'a>(
     app: &App,
     user: &User,
@@ -194,37 +589,132 @@ fn render_user_timeline_view<'a>(
     row().push(
         column()
             .push(view_user_timeline_title(&user))
+            .push(view_user_profile(&user))
             .push(view_navigation(app))
-            .push(row().push(view_user_tweets(tweets, app.config.tweets_per_page)))
+            .push(if tweets.is_empty() {
+                column().push(view_missing(missing_from_archive(&format!(
+                    "@{}'s timeline",
+                    user.username
+                ))))
+            } else {
+                view_user_tweets(tweets, app.config.tweets_per_page)
+            })
             .spacing(10),
     )
 }
 
-fn render_tweet_view<'a>(app: &App, tweet_data: &TweetData) -> Row<'a, Message> {
+fn render_missing_view<'a>(app: &App, description: &str) -> Row<'a, Message> {
     row().push(
         column()
-            .push(view_tweet_title(&tweet_data))
+            .push(
+                text("Not in the archive")
+                    .size(30)
+                    .width(Length::Fill)
+                    .horizontal_alignment(iced::alignment::Horizontal::Center),
+            )
             .push(view_navigation(app))
-            .push(row().push(view_tweet(&tweet_data)))
+            .push(view_missing(description.to_string()))
             .spacing(10),
     )
 }
 
-fn render_conversation_view<'a>(app: &App, conversation: &Vec<TweetData>) -> Row<'a, Message> {
-    if &conversation.len() > &1 {
+/// Says why something isn't shown, which offline means it was never archived.
+fn missing_from_archive(item: &str) -> String {
+    if app::server::is_offline() {
+        format!("{} isn't archived and can't be fetched while offline", item)
+    } else {
+        format!("{} couldn't be found in the archive or on the server", item)
+    }
+}
+
+fn view_missing(description: String) -> Text {
+    text(description)
+        .horizontal_alignment(iced::alignment::Horizontal::Center)
+        .width(Length::Fill)
+        .size(20)
+}
+
+fn render_profile_history_view<'a>(
+    app: &App,
+    user: &User,
+    history: &Vec<UserSnapshotData>,
+) -> Row<'a, Message> {
+    row().push(
+        column()
+            .push(view_profile_history_title(&user))
+            .push(view_navigation(app))
+            .push(view_profile_history(history))
+            .spacing(10),
+    )
+}
+
+fn render_tweet_view<'a>(
+    app: &App,
+    tweet_data: &TweetData,
+    references: &TweetReferencesData,
+) -> Row<'a, Message> {
+    let tweet_page = column()
+        .push(view_tweet_title(&tweet_data))
+        .push(view_navigation(app));
+    let tweet_page = match &references.replying_to {
+        Some(parent) => tweet_page.push(view_parent_tweet_preview(parent)),
+        None => tweet_page,
+    };
+    row().push(
+        tweet_page
+            .push(view_tweet_detail(&tweet_data, references))
+            .push(view_tweet_actions(&tweet_data))
+            .push(view_tweet_references(references))
+            .spacing(10),
+    )
+}
+
+fn render_conversation_view<'a>(
+    app: &App,
+    conversation: &ConversationTree,
+    selected_tweet_id: &u64,
+    collapsed_tweet_ids: &HashSet<u64>,
+    is_unrolled: &bool,
+) -> Row<'a, Message> {
+    let thread = conversation.self_thread(*selected_tweet_id);
+    if let (Some(thread), true) = (&thread, *is_unrolled) {
+        return row().push(
+            column()
+                .push(view_thread_title(thread))
+                .push(view_navigation(app))
+                .push(view_thread_actions(true))
+                .push(view_unrolled_thread(thread))
+                .spacing(10),
+        );
+    }
+
+    let selected_tweet = conversation
+        .nodes
+        .iter()
+        .find(|node| node.tweet_data.tweet.id.as_u64() == *selected_tweet_id)
+        .unwrap_or(&conversation.nodes[0]);
+    if &conversation.nodes.len() > &1 {
         row().push(
             column()
-                .push(view_conversation_title(&conversation[0]))
+                .push(view_conversation_title(&selected_tweet.tweet_data))
                 .push(view_navigation(app))
-                .push(view_conversation(conversation))
+                .push(match thread {
+                    Some(_) => view_thread_actions(false),
+                    None => row(),
+                })
+                .push(view_conversation(
+                    conversation,
+                    selected_tweet_id,
+                    collapsed_tweet_ids,
+                ))
                 .spacing(10),
         )
     } else {
         row().push(
             column()
-                .push(column().push(view_tweet_title(&conversation[0])))
+                .push(column().push(view_tweet_title(&selected_tweet.tweet_data)))
                 .push(view_navigation(app))
-                .push(view_tweet(&conversation[0]))
+                .push(view_tweet(&selected_tweet.tweet_data))
                 .spacing(10),
         )
     }
@@ -233,7 +723,9 @@ fn render_conversation_view<'a>(app: &App, conversation: &Vec<TweetData>) -> Row
 fn render_search_view<'a>(
     app: &App,
     search_query: &str,
-    search_results: &Vec<TweetData>,
+    options: &SearchOptions,
+    search_results: &Vec<SearchResult>,
+    suggestions: &Vec<String>,
 ) -> Row<'a, Message> {
     let number_of_results = &search_results.len();
     if &search_results.len() > &0 {
@@ -241,7 +733,8 @@ fn render_search_view<'a>(
             column()
                 .push(view_search_title(number_of_results, &search_query))
                 .push(view_navigation(app))
-                .push(view_tweets(&search_results))
+                .push(view_search_options(options))
+                .push(view_search_results(&search_results))
                 .spacing(10),
         )
     } else {
@@ -249,12 +742,14 @@ fn render_search_view<'a>(
             column()
                 .push(view_search_title(number_of_results, &search_query))
                 .push(view_navigation(app))
+                .push(view_search_options(options))
                 .push(
                     text("Sorry, no results found")
                         .horizontal_alignment(iced::alignment::Horizontal::Center)
                         .width(Length::Fill)
                         .size(20),
                 )
+                .push(view_search_suggestions(suggestions))
                 .spacing(10),
         )
     }
@@ -265,21 +760,145 @@ fn view_tweet<'a>(tweet_data: &TweetData) -> Button<'a, Message> {
         column()
             .push(
                 row()
-                    .push(view_tweet_author_name(&tweet_data.user))
+                    .push(view_tweet_author_name(tweet_data))
                     .push(view_tweet_datetime(&tweet_data.tweet))
                     .spacing(30),
             )
-            .push(row().push(text(&tweet_data.tweet.text)))
+            .push(view_tweet_text(&tweet_data.tweet, TWEET_TEXT_SIZE))
             .spacing(10),
     )
     .style(style::Tweet)
     .width(Length::Fill)
     .padding(20)
-    .on_press(Message::DisplayConversation(tweet_data.clone()))
+    .on_press(Message::DisplayTweet(tweet_data.clone()))
+}
+
+fn view_parent_tweet_preview<'a>(parent: &TweetData) -> Column<'a, Message> {
+    column()
+        .push(text(format!("Replying to {}", parent.author_label())).size(15))
+        .push(view_tweet(parent))
+        .spacing(5)
+}
+
+fn view_tweet_detail<'a>(
+    tweet_data: &TweetData,
+    references: &TweetReferencesData,
+) -> Element<'a, Message> {
+    let detail = column()
+        .push(view_tweet_author_name(tweet_data))
+        .push(view_tweet_text(&tweet_data.tweet, TWEET_TEXT_SIZE));
+
+    let embedded_tweets = [
+        ("Quoting", &references.quoting),
+        ("Retweet of", &references.retweet_of),
+    ];
+    let detail = embedded_tweets
+        .into_iter()
+        .fold(detail, |detail, (label, embedded_tweet)| match embedded_tweet {
+            Some(embedded_tweet) => detail
+                .push(text(label).size(15))
+                .push(view_tweet(embedded_tweet).style(style::EmbeddedTweet)),
+            None => detail,
+        });
+
+    let detail = detail.push(text(get_tweet_full_datetime_string(&tweet_data.tweet)).size(15));
+    let detail = match &tweet_data.tweet.public_metrics {
+        Some(metrics) => detail.push(
+            text(format!(
+                "{} replies  ·  {} retweets  ·  {} likes",
+                metrics.reply_count, metrics.retweet_count, metrics.like_count
+            ))
+            .size(15),
+        ),
+        None => detail,
+    };
+
+    container(detail.spacing(15))
+        .style(style::Panel)
+        .width(Length::Fill)
+        .padding(20)
+        .into()
+}
+
+fn view_tweet_actions<'a>(tweet_data: &TweetData) -> Row<'a, Message> {
+    let actions = row().push(view_navigation_button(
+        "Open conversation",
+        Message::DisplayConversation(tweet_data.clone()),
+        true,
+    ));
+    match &tweet_data.user {
+        Some(user) => actions.push(view_navigation_button(
+            &format!("@{}'s timeline", user.twitter_handle),
+            Message::DisplayUserByTwitterHandle(user.twitter_handle.clone()),
+            true,
+        )),
+        None => actions,
+    }
+    .spacing(20)
+}
+
+// an author missing from the archive has no profile to go to
+fn view_tweet_author_name<'a>(tweet_data: &TweetData) -> Button<'a, Message> {
+    match &tweet_data.user {
+        Some(user) => button(text(format!("{} (@{})", user.name, user.twitter_handle)).size(15))
+            .style(style::Link)
+            .padding(0)
+            .on_press(Message::DisplayUserByTwitterHandle(
+                user.twitter_handle.clone(),
+            )),
+        None => button(
+            text(format!(
+                "Unknown author (id {})",
+                tweet_data
+                    .tweet
+                    .author_id
+                    .map(|author_id| author_id.as_u64().to_string())
+                    .unwrap_or_default()
+            ))
+            .size(15),
+        )
+        .style(style::Link)
+        .padding(0),
+    }
 }
 
-fn view_tweet_author_name<'a>(user: &UserData) -> Text {
-    text(format!("{} (@{})", user.name, user.twitter_handle)).size(15)
+fn view_text_link<'a>(label: String, size: u16, action: Message) -> Button<'a, Message> {
+    button(text(label).size(size))
+        .style(style::Link)
+        .padding(0)
+        .on_press(action)
+}
+
+const TWEET_TEXT_SIZE: u16 = 20;
+// iced can't wrap text around buttons, so tweet text is wrapped by hand at
+// roughly the number of characters that fit across a tweet
+const TWEET_TEXT_LINE_LENGTH: usize = 55;
+
+fn view_tweet_text<'a>(tweet: &Tweet, size: u16) -> Column<'a, Message> {
+    tweet_text_lines(tweet, TWEET_TEXT_LINE_LENGTH)
+        .into_iter()
+        .map(|line| {
+            line.into_iter()
+                .fold(row(), |line_view, segment| match segment {
+                    TweetTextSegment::Text(segment_text) => {
+                        line_view.push(text(segment_text).size(size))
+                    }
+                    TweetTextSegment::Mention(twitter_handle) => line_view.push(view_text_link(
+                        format!("@{}", twitter_handle),
+                        size,
+                        Message::DisplayUserByTwitterHandle(twitter_handle),
+                    )),
+                    TweetTextSegment::Hashtag(tag) => line_view.push(view_text_link(
+                        format!("#{}", tag),
+                        size,
+                        Message::Search(format!("#{}", tag)),
+                    )),
+                    TweetTextSegment::Link { display_url, url } => line_view.push(
+                        view_text_link(display_url, size, Message::OpenUrl(url)),
+                    ),
+                })
+        })
+        .fold(column(), |text_view, line_view| text_view.push(line_view))
 }
 
 fn view_tweet_datetime(tweet: &Tweet) -> Text {
@@ -305,6 +924,37 @@ fn view_tweets<'a>(tweets: &Vec<TweetData>) -> Column<'a, Message> {
         .spacing(15)
 }
 
+fn view_search_results<'a>(search_results: &Vec<SearchResult>) -> Column<'a, Message> {
+    search_results
+        .iter()
+        .map(|search_result| view_search_result(search_result))
+        .fold(column(), |results_view, result_view| {
+            results_view.push(result_view)
+        })
+        .spacing(15)
+}
+
+fn view_search_result<'a>(search_result: &SearchResult) -> Column<'a, Message> {
+    let snippet = search_result.snippet_segments().into_iter().fold(
+        row(),
+        |snippet, (segment, is_highlighted)| {
+            if is_highlighted {
+                snippet.push(
+                    text(segment)
+                        .size(15)
+                        .color(theme::SEARCH_HIGHLIGHT_TEXT_COLOR),
+                )
+            } else {
+                snippet.push(text(segment).size(15))
+            }
+        },
+    );
+    column()
+        .push(snippet.padding([0, 20]))
+        .push(view_tweet(&search_result.tweet_data))
+        .spacing(5)
+}
+
 fn view_tweets_paginated<'a>(
     tweets: &Vec<TweetData>,
     tweets_per_page: usize,
@@ -350,6 +1000,102 @@ fn view_user_tweets<'a>(tweets: &Vec<TweetData>, tweets_per_page: usize) -> Colu
         .spacing(25)
 }
 
+fn view_home_title() -> Text {
+    text("Tracked accounts")
+        .size(30)
+        .width(Length::Fill)
+        .horizontal_alignment(iced::alignment::Horizontal::Center)
+}
+
+fn view_track_account<'a>(track_input: &String, is_idle: bool) -> Row<'a, Message> {
+    row()
+        .push(
+            text_input(
+                "handle to track, e.g. yudapearl",
+                track_input,
+                Message::TrackInputChanged,
+            )
+            .size(15)
+            .width(Length::Fill)
+            .padding(8)
+            .style(style::SearchBar)
+            .on_submit(Message::TrackAccount),
+        )
+        .push(view_navigation_button(
+            "Track",
+            Message::TrackAccount,
+            is_idle && !track_input.trim().is_empty(),
+        ))
+        .align_items(Alignment::Center)
+        .spacing(20)
+}
+
+fn view_tracked_accounts<'a>(tracked_accounts: &Vec<TrackedAccountData>) -> Column<'a, Message> {
+    if tracked_accounts.is_empty() {
+        return column().push(
+            text("No accounts are tracked yet, add a handle above to start archiving")
+                .horizontal_alignment(iced::alignment::Horizontal::Center)
+                .width(Length::Fill)
+                .size(20),
+        );
+    }
+
+    tracked_accounts
+        .iter()
+        .fold(column(), |accounts_view, tracked_account| {
+            accounts_view.push(view_tracked_account(tracked_account))
+        })
+        .spacing(15)
+}
+
+fn view_tracked_account<'a>(tracked_account: &TrackedAccountData) -> Element<'a, Message> {
+    let user = &tracked_account.user;
+    let last_synced = match tracked_account.last_synced_at {
+        Some(last_synced_at) => format!(
+            "last synced {}",
+            last_synced_at.format("%Y/%m/%d at %H:%M")
+        ),
+        None => "never synced".to_string(),
+    };
+    container(
+        row()
+            .push(
+                column()
+                    .push(
+                        button(text(format!("{} (@{})", user.name, user.username)).size(20))
+                            .on_press(Message::DisplayUsersTweets(user.clone()))
+                            .padding(0)
+                            .style(style::Link),
+                    )
+                    .push(
+                        text(format!(
+                            "{} tweets archived · {}",
+                            tracked_account.tweet_count, last_synced
+                        ))
+                        .size(15),
+                    )
+                    .spacing(5)
+                    .width(Length::Fill),
+            )
+            .push(view_navigation_button(
+                "Stop tracking",
+                Message::UntrackAccount(
+                    user.id
+                        .as_u64()
+                        .try_into()
+                        .expect("Failed to parse u64 into i64"),
+                ),
+                true,
+            ))
+            .align_items(Alignment::Center)
+            .spacing(20),
+    )
+    .style(style::Panel)
+    .width(Length::Fill)
+    .padding(20)
+    .into()
+}
+
 fn view_user_timeline_title(user: &User) -> Text {
     text(format!("{}'s (@{}) Tweets", user.name, user.username))
         .size(30)
@@ -357,18 +1103,295 @@ fn view_user_timeline_title(user: &User) -> Text {
         .horizontal_alignment(iced::alignment::Horizontal::Center)
 }
 
-fn view_conversation<'a>(conversation: &Vec<TweetData>) -> Column<'a, Message> {
-    let mut display_conversation = conversation.clone();
-    display_conversation.reverse();
+fn view_user_profile<'a>(user: &User) -> Column<'a, Message> {
+    let mut details: Vec<String> = Vec::new();
+    if let Some(location) = &user.location {
+        details.push(location.clone());
+    }
+    if let Some(url) = &user.url {
+        details.push(url.to_string());
+    }
+    if let Some(created_at) = user.created_at {
+        let format = time::format_description::parse("Joined [month repr:long] [year]")
+            .expect("failed to get format");
+        details.push(created_at.format(&format).expect("Failed to format datetime"));
+    }
+    if user.verified == Some(true) {
+        details.push("Verified".to_string());
+    }
+    if user.protected == Some(true) {
+        details.push("Protected".to_string());
+    }
+    details.retain(|detail| !detail.is_empty());
+
+    let profile = column()
+        .push(
+            text(user.description.clone().unwrap_or_default())
+                .size(15)
+                .width(Length::Fill)
+                .horizontal_alignment(iced::alignment::Horizontal::Center),
+        )
+        .push(
+            text(details.join("  ·  "))
+                .size(15)
+                .width(Length::Fill)
+                .horizontal_alignment(iced::alignment::Horizontal::Center),
+        )
+        .spacing(5);
+
+    let profile = profile.push(
+        row()
+            .push(column().width(Length::Fill))
+            .push(view_navigation_button(
+                "Profile history",
+                Message::DisplayProfileHistory(user.clone()),
+                true,
+            ))
+            .push(column().width(Length::Fill)),
+    );
+
+    match &user.public_metrics {
+        Some(metrics) => profile.push(
+            text(format!(
+                "{} followers  ·  {} following  ·  {} tweets  ·  {} listed",
+                metrics.followers_count,
+                metrics.following_count,
+                metrics.tweet_count,
+                metrics.listed_count
+            ))
+            .size(15)
+            .width(Length::Fill)
+            .horizontal_alignment(iced::alignment::Horizontal::Center),
+        ),
+        None => profile,
+    }
+}
+
+fn view_profile_history_title(user: &User) -> Text {
+    text(format!("Profile history of {} (@{})", user.name, user.username))
+        .size(30)
+        .width(Length::Fill)
+        .horizontal_alignment(iced::alignment::Horizontal::Center)
+}
+
+fn view_profile_history<'a>(history: &Vec<UserSnapshotData>) -> Column<'a, Message> {
+    if history.is_empty() {
+        return column().push(
+            text("No profile snapshots have been archived for this user yet")
+                .horizontal_alignment(iced::alignment::Horizontal::Center)
+                .width(Length::Fill)
+                .size(20),
+        );
+    }
+
+    // newest changes first, the oldest snapshot describes the profile as first archived
+    history
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, snapshot)| match i {
+            0 => view_profile_history_entry(snapshot, "First archived", snapshot.summary()),
+            _ => view_profile_history_entry(
+                snapshot,
+                "Changed",
+                snapshot.changes_since(&history[i - 1]),
+            ),
+        })
+        .fold(column(), |history_view, entry| history_view.push(entry))
+        .spacing(15)
+}
+
+fn view_profile_history_entry<'a>(
+    snapshot: &UserSnapshotData,
+    label: &str,
+    changes: Vec<String>,
+) -> Element<'a, Message> {
+    let details = changes
+        .into_iter()
+        .fold(column(), |details, change| {
+            details.push(text(change).size(15))
+        })
+        .spacing(5);
+    container(
+        column()
+            .push(
+                text(format!(
+                    "{} on {}",
+                    label,
+                    snapshot.fetched_at.format("%Y/%m/%d at %H:%M")
+                ))
+                .size(15),
+            )
+            .push(details)
+            .spacing(10),
+    )
+    .style(style::Panel)
+    .width(Length::Fill)
+    .padding(20)
+    .into()
+}
+
+fn view_tweet_references<'a>(references: &TweetReferencesData) -> Column<'a, Message> {
+    // what the tweet itself replies to, quotes or retweets is shown with it
+    let referencing_tweets = [
+        ("Replies", &references.replies),
+        ("Quoted by", &references.quotes),
+        ("Retweeted by", &references.retweets),
+    ];
+
+    referencing_tweets
+        .into_iter()
+        .filter(|(_, tweets)| !tweets.is_empty())
+        .fold(column(), |references_view, (label, tweets)| {
+            references_view
+                .push(view_section_title(&format!("{} ({})", label, tweets.len())))
+                .push(view_tweets(tweets))
+        })
+        .spacing(15)
+}
+
+fn view_section_title(title: &str) -> Text {
+    text(title)
+        .size(20)
+        .width(Length::Fill)
+        .horizontal_alignment(iced::alignment::Horizontal::Center)
+}
+
+fn view_thread_title<'a>(thread: &UnrolledThread) -> Column<'a, Message> {
+    let author = thread.author();
     column()
-        .push(view_tweets(&display_conversation))
-        .spacing(25)
+        .push(
+            text(format!("Thread by {} (@{})", author.name, author.twitter_handle))
+                .size(30)
+                .width(Length::Fill)
+                .horizontal_alignment(iced::alignment::Horizontal::Center),
+        )
+        .push(
+            text(format!(
+                "{} tweets, posted on {}",
+                thread.tweets.len(),
+                get_tweet_created_datetime_string(&thread.tweets[0].tweet)
+            ))
+            .size(15)
+            .width(Length::Fill)
+            .horizontal_alignment(iced::alignment::Horizontal::Center),
+        )
+        .spacing(5)
+}
+
+fn view_thread_actions<'a>(is_unrolled: bool) -> Row<'a, Message> {
+    let toggle_label = if is_unrolled {
+        "Show full conversation"
+    } else {
+        "Read as thread"
+    };
+    let actions = row().push(view_navigation_button(
+        toggle_label,
+        Message::ToggleUnrolledThread,
+        true,
+    ));
+    if !is_unrolled {
+        return actions;
+    }
+    actions
+        .push(column().width(Length::Fill))
+        .push(view_navigation_button(
+            "Copy text",
+            Message::CopyThread(ThreadFormat::PlainText),
+            true,
+        ))
+        .push(view_navigation_button(
+            "Copy Markdown",
+            Message::CopyThread(ThreadFormat::Markdown),
+            true,
+        ))
+        .push(view_navigation_button(
+            "Export text",
+            Message::ExportThread(ThreadFormat::PlainText),
+            true,
+        ))
+        .push(view_navigation_button(
+            "Export Markdown",
+            Message::ExportThread(ThreadFormat::Markdown),
+            true,
+        ))
+        .spacing(20)
+}
+
+fn view_unrolled_thread<'a>(thread: &UnrolledThread) -> Element<'a, Message> {
+    let paragraphs = thread
+        .paragraphs()
+        .into_iter()
+        .fold(column(), |paragraphs_view, paragraph| {
+            paragraphs_view.push(text(paragraph).size(17))
+        })
+        .spacing(15);
+    container(paragraphs)
+        .style(style::Panel)
+        .width(Length::Fill)
+        .padding(30)
+        .into()
+}
+
+// deeper replies stop indenting so long chains keep a readable width
+const MAX_CONVERSATION_INDENT_DEPTH: usize = 8;
+const CONVERSATION_INDENT_WIDTH: u16 = 30;
+
+fn view_conversation<'a>(
+    conversation: &ConversationTree,
+    selected_tweet_id: &u64,
+    collapsed_tweet_ids: &HashSet<u64>,
+) -> Column<'a, Message> {
+    conversation
+        .visible_nodes(collapsed_tweet_ids)
+        .into_iter()
+        .map(|node| view_conversation_node(node, selected_tweet_id, collapsed_tweet_ids))
+        .fold(column(), |conversation_view, node_view| {
+            conversation_view.push(node_view)
+        })
+        .spacing(15)
+}
+
+fn view_conversation_node<'a>(
+    node: &ConversationNode,
+    selected_tweet_id: &u64,
+    collapsed_tweet_ids: &HashSet<u64>,
+) -> Row<'a, Message> {
+    let tweet_id = node.tweet_data.tweet.id.as_u64();
+    let tweet_view = if tweet_id == *selected_tweet_id {
+        view_tweet(&node.tweet_data).style(style::HighlightedTweet)
+    } else {
+        view_tweet(&node.tweet_data)
+    };
+
+    let node_view = column().push(tweet_view).spacing(5);
+    let node_view = match node.reply_count {
+        0 => node_view,
+        reply_count => {
+            let plural = if reply_count == 1 { "reply" } else { "replies" };
+            let label = if collapsed_tweet_ids.contains(&tweet_id) {
+                format!("Show {} {}", reply_count, plural)
+            } else {
+                format!("Hide {} {}", reply_count, plural)
+            };
+            node_view.push(view_navigation_button(
+                &label,
+                Message::ToggleReplies(tweet_id),
+                true,
+            ))
+        }
+    };
+
+    let indent = node.depth.min(MAX_CONVERSATION_INDENT_DEPTH) as u16 * CONVERSATION_INDENT_WIDTH;
+    row()
+        .push(column().width(Length::Units(indent)))
+        .push(node_view.width(Length::Fill))
 }
 
 fn view_conversation_title(tweet_data: &TweetData) -> Text {
     text(format!(
-        "Conversation containing @{}'s tweet posted on {}",
-        tweet_data.user.twitter_handle,
+        "Conversation containing {}'s tweet posted on {}",
+        tweet_data.author_label(),
         get_tweet_created_datetime_string(&tweet_data.tweet)
     ))
     .size(30)
@@ -378,8 +1401,8 @@ fn view_conversation_title(tweet_data: &TweetData) -> Text {
 
 fn view_tweet_title(tweet_data: &TweetData) -> Text {
     text(format!(
-        "Tweet by @{} posted at {}",
-        tweet_data.user.twitter_handle,
+        "Tweet by {} posted at {}",
+        tweet_data.author_label(),
         get_tweet_created_datetime_string(&tweet_data.tweet)
     ))
     .size(30)
@@ -390,47 +1413,84 @@ fn view_tweet_title(tweet_data: &TweetData) -> Text {
 fn view_navigation<'a>(app: &App) -> Row<'a, Message> {
     let is_back_button_active: bool = app.model.previous.len() > 0;
     let is_forward_button_active: bool = app.model.next.len() > 0;
-    let is_home_button_active: bool =
-        app.model.previous.len() > 0 && app.model.previous[0] != app.model.selected;
+    let is_home_button_active: bool = !matches!(app.model.selected, Snapshot::HomeView(_));
     row()
         .push(
-            column().push(
-                row()
-                    .push(view_navigation_button(
-                        "Back",
-                        Message::Back,
-                        is_back_button_active,
-                    ))
-                    .push(view_navigation_button(
-                        "Forward",
-                        Message::Forward,
-                        is_forward_button_active,
-                    ))
-                    .push(view_navigation_button(
-                        "Home",
-                        Message::Home,
-                        is_home_button_active,
-                    ))
-                    .push(view_navigation_button(
-                        "Seed",
-                        Message::SeedConversations,
-                        true,
-                    ))
-                    .spacing(20),
-            ),
+            column()
+                .push(
+                    row()
+                        .push(view_navigation_button(
+                            "Back",
+                            Message::Back,
+                            is_back_button_active,
+                        ))
+                        .push(view_navigation_button(
+                            "Forward",
+                            Message::Forward,
+                            is_forward_button_active,
+                        ))
+                        .push(view_navigation_button(
+                            "Home",
+                            Message::Home,
+                            is_home_button_active,
+                        ))
+                        .push(view_navigation_button(
+                            "Seed",
+                            Message::SeedConversations,
+                            app.background_task.is_none(),
+                        ))
+                        .push(view_navigation_button(
+                            if app::server::is_offline() {
+                                "Go online"
+                            } else {
+                                "Go offline"
+                            },
+                            Message::ToggleOffline,
+                            true,
+                        ))
+                        .spacing(20),
+                )
+                .push(view_offline_indicator())
+                .push(view_background_task(&app.background_task))
+                .push(view_open_url_error(&app.open_url_error)),
         )
         .push(column().width(Length::Fill))
         .push(
             column()
                 .push(row().push(view_search(&app.search_input)))
+                .push(view_search_error(&app.search_error))
                 .align_items(iced::Alignment::End),
         )
         .spacing(100)
 }
 
+fn view_offline_indicator() -> Text {
+    if app::server::is_offline() {
+        text("Offline, showing only what's archived")
+            .size(13)
+            .color(theme::ERROR_TEXT_COLOR)
+    } else {
+        text("")
+    }
+}
+
+fn view_background_task(background_task: &Option<String>) -> Text {
+    match background_task {
+        Some(background_task) => text(background_task).size(13),
+        None => text(""),
+    }
+}
+
+fn view_open_url_error(open_url_error: &Option<String>) -> Text {
+    match open_url_error {
+        Some(error) => text(error).size(13).color(theme::ERROR_TEXT_COLOR),
+        None => text(""),
+    }
+}
+
 fn view_search<'a>(search_input: &String) -> TextInput<'a, Message> {
     text_input(
-        "type to search then press enter",
+        "search, e.g. from:yudapearl since:2020-01-01",
         search_input,
         Message::SearchInputChanged,
     )
@@ -441,6 +1501,57 @@ fn view_search<'a>(search_input: &String) -> TextInput<'a, Message> {
     .on_submit(Message::Search(search_input.to_string()))
 }
 
+fn view_search_error(search_error: &Option<String>) -> Text {
+    match search_error {
+        Some(error) => text(error)
+            .size(13)
+            .width(Length::Units(200))
+            .color(theme::ERROR_TEXT_COLOR),
+        None => text(""),
+    }
+}
+
+fn view_search_options<'a>(options: &SearchOptions) -> Row<'a, Message> {
+    SearchOrder::ALL
+        .iter()
+        .fold(row().push(text("Sort by").size(15)), |orders, order| {
+            orders.push(view_navigation_button(
+                order.label(),
+                Message::SortSearch(*order),
+                *order != options.order,
+            ))
+        })
+        .push(column().width(Length::Fill))
+        .push(view_navigation_button(
+            if options.is_fuzzy {
+                "Fuzzy matching: on"
+            } else {
+                "Fuzzy matching: off"
+            },
+            Message::FuzzySearch(!options.is_fuzzy),
+            true,
+        ))
+        .align_items(Alignment::Center)
+        .spacing(20)
+}
+
+fn view_search_suggestions<'a>(suggestions: &Vec<String>) -> Row<'a, Message> {
+    if suggestions.is_empty() {
+        return row();
+    }
+    suggestions
+        .iter()
+        .fold(row().push(text("Did you mean").size(15)), |suggestions_view, suggestion| {
+            suggestions_view.push(view_navigation_button(
+                suggestion,
+                Message::Search(suggestion.clone()),
+                true,
+            ))
+        })
+        .align_items(Alignment::Center)
+        .spacing(20)
+}
+
 fn view_search_title(number_of_results: &usize, search_query: &str) -> Text {
     text(format!(
         "{} results for search: \"{}\"",
@@ -468,6 +1579,19 @@ fn view_navigation_button<'a>(
     }
 }
 
+fn get_tweet_full_datetime_string(tweet: &Tweet) -> String {
+    let format = time::format_description::parse(
+        "Posted on [weekday repr:long], [month repr:long] [day padding:none], [year] \
+         at [hour]:[minute]:[second] UTC[offset_hour sign:mandatory]:[offset_minute]",
+    )
+    .expect("failed to get format");
+    tweet
+        .created_at
+        .expect("failed to parse date")
+        .format(&format)
+        .expect("Failed to format datetime")
+}
+
 fn get_tweet_created_datetime_string(tweet: &Tweet) -> String {
     let format = time::format_description::parse("[year]/[month]/[day] at [hour]:[minute]")
         .expect("failed to get format");
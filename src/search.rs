@@ -0,0 +1,501 @@
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A word or quoted phrase to look for in tweet text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    Word(String),
+    Phrase(String),
+}
+
+impl Term {
+    pub fn text(&self) -> &str {
+        match self {
+            Term::Word(text) => text,
+            Term::Phrase(text) => text,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    Reply,
+    Thread,
+    Link,
+}
+
+/// How search results are ordered. Relevance only differs from newest first
+/// where the database has a full text index to score matches with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchOrder {
+    Relevance,
+    Newest,
+    Oldest,
+}
+
+impl SearchOrder {
+    pub const ALL: [SearchOrder; 3] = [
+        SearchOrder::Relevance,
+        SearchOrder::Newest,
+        SearchOrder::Oldest,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SearchOrder::Relevance => "Most relevant",
+            SearchOrder::Newest => "Newest",
+            SearchOrder::Oldest => "Oldest",
+        }
+    }
+}
+
+/// Everything about a search besides the query itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchOptions {
+    pub order: SearchOrder,
+    /// Also match archived words a typo or two away from the searched ones.
+    pub is_fuzzy: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            order: SearchOrder::Relevance,
+            is_fuzzy: false,
+        }
+    }
+}
+
+/// A search box query such as `from:yudapearl since:2020-01-01 "do calculus" -bayes`.
+///
+/// Words and phrases must all match, except that words joined by `OR` form a
+/// group where any one is enough. `from:` and `to:` may be repeated to match
+/// any of several users, and `until:` excludes the day it names.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    pub term_groups: Vec<Vec<Term>>,
+    pub excluded_terms: Vec<Term>,
+    pub from: Vec<String>,
+    pub to: Vec<String>,
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+    /// Each filter paired with whether it must hold (`is:reply`) or must not
+    /// hold (`-is:reply`).
+    pub filters: Vec<(Filter, bool)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchQueryError(pub String);
+
+impl fmt::Display for SearchQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// anything else before a colon, like `Re:` or `https:`, is part of a word
+const OPERATORS: [&str; 6] = ["from", "to", "since", "until", "is", "has"];
+
+enum Token {
+    Term { term: Term, is_negated: bool },
+    Operator { name: String, value: String, is_negated: bool },
+    Or,
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> Result<Self, SearchQueryError> {
+        let mut query = SearchQuery::default();
+        let mut is_or_pending = false;
+        let mut can_join_with_or = false;
+
+        for token in tokenize(input)? {
+            match token {
+                Token::Term {
+                    term,
+                    is_negated: true,
+                } => {
+                    if is_or_pending {
+                        return Err(or_error());
+                    }
+                    query.excluded_terms.push(term);
+                    can_join_with_or = false;
+                }
+                Token::Term {
+                    term,
+                    is_negated: false,
+                } => {
+                    match query.term_groups.last_mut() {
+                        Some(group) if is_or_pending => group.push(term),
+                        _ => query.term_groups.push(vec![term]),
+                    }
+                    is_or_pending = false;
+                    can_join_with_or = true;
+                }
+                Token::Or => {
+                    if !can_join_with_or || is_or_pending {
+                        return Err(or_error());
+                    }
+                    is_or_pending = true;
+                }
+                Token::Operator {
+                    name,
+                    value,
+                    is_negated,
+                } => {
+                    if is_or_pending {
+                        return Err(or_error());
+                    }
+                    query.apply_operator(&name, &value, is_negated)?;
+                    can_join_with_or = false;
+                }
+            }
+        }
+
+        if is_or_pending {
+            return Err(or_error());
+        }
+
+        Ok(query)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == SearchQuery::default()
+    }
+
+    /// The lowercased single words being searched for, which are the ones that
+    /// can be checked for typos. Phrases and words with punctuation are left out.
+    pub fn words(&self) -> Vec<String> {
+        let mut words: Vec<String> = Vec::new();
+        for term in self.term_groups.iter().flatten() {
+            if let Term::Word(word) = term {
+                let word = word.to_lowercase();
+                if word.chars().all(|c| c.is_alphanumeric()) && !words.contains(&word) {
+                    words.push(word);
+                }
+            }
+        }
+        words
+    }
+
+    /// Widens every word into an `OR` group with its spelling variants, so a
+    /// variant matching counts the same as the word itself.
+    pub fn with_variants(&self, variants: &HashMap<String, Vec<String>>) -> SearchQuery {
+        let mut query = self.clone();
+        query.term_groups = self
+            .term_groups
+            .iter()
+            .map(|group| {
+                let mut widened_group = group.clone();
+                for term in group {
+                    if let Term::Word(word) = term {
+                        for variant in variants.get(&word.to_lowercase()).into_iter().flatten() {
+                            let variant = Term::Word(variant.clone());
+                            if !widened_group.contains(&variant) {
+                                widened_group.push(variant);
+                            }
+                        }
+                    }
+                }
+                widened_group
+            })
+            .collect();
+        query
+    }
+
+    fn apply_operator(
+        &mut self,
+        name: &str,
+        value: &str,
+        is_negated: bool,
+    ) -> Result<(), SearchQueryError> {
+        let name_lowercase = name.to_lowercase();
+        match name_lowercase.as_str() {
+            "is" | "has" => {
+                let filter = match (name_lowercase.as_str(), value.to_lowercase().as_str()) {
+                    ("is", "reply") => Filter::Reply,
+                    ("is", "thread") => Filter::Thread,
+                    ("has", "link") | ("has", "links") => Filter::Link,
+                    _ => {
+                        return Err(SearchQueryError(format!(
+                            "Unknown filter {}:{}, try is:reply, is:thread or has:link",
+                            name, value
+                        )))
+                    }
+                };
+                self.filters.push((filter, !is_negated));
+                Ok(())
+            }
+            _ if is_negated => Err(SearchQueryError(format!(
+                "{}: can't be excluded with -",
+                name
+            ))),
+            "from" => {
+                self.from.push(value.trim_start_matches('@').to_string());
+                Ok(())
+            }
+            "to" => {
+                self.to.push(value.trim_start_matches('@').to_string());
+                Ok(())
+            }
+            "since" => {
+                self.since = Some(parse_date(name, value)?);
+                Ok(())
+            }
+            "until" => {
+                self.until = Some(parse_date(name, value)?);
+                Ok(())
+            }
+            _ => unreachable!("Only known operators are tokenized as operators"),
+        }
+    }
+}
+
+/// Rewrites a search box input with some of its words respelled, leaving
+/// operators, phrases and everything else as typed.
+pub fn respell(input: &str, corrections: &HashMap<String, String>) -> String {
+    input
+        .split(' ')
+        .map(|word| match corrections.get(&word.to_lowercase()) {
+            Some(correction) => correction.clone(),
+            None => word.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// How many typos a word may have and still be considered a misspelling of
+/// another. Short words get none, otherwise almost anything would match.
+pub fn typo_allowance(word: &str) -> usize {
+    match word.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// The Levenshtein distance, the number of single character insertions,
+/// deletions and substitutions that turn one word into the other.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous_row[j] + if a_char == *b_char { 0 } else { 1 };
+            row.push(substitution.min(previous_row[j + 1] + 1).min(row[j] + 1));
+        }
+        previous_row = row;
+    }
+    previous_row[b.len()]
+}
+
+fn or_error() -> SearchQueryError {
+    SearchQueryError("OR has to come between two words or phrases".to_string())
+}
+
+fn parse_date(name: &str, value: &str) -> Result<NaiveDate, SearchQueryError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        SearchQueryError(format!(
+            "{}: expects a date like 2020-01-01, not \"{}\"",
+            name, value
+        ))
+    })
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, SearchQueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut is_negated = false;
+        if c == '-' {
+            chars.next();
+            match chars.peek() {
+                Some(next) if !next.is_whitespace() => is_negated = true,
+                // a lone dash is just punctuation
+                _ => continue,
+            }
+        }
+
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            let mut phrase = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => phrase.push(c),
+                    None => {
+                        return Err(SearchQueryError(
+                            "A quoted phrase is missing its closing quote".to_string(),
+                        ))
+                    }
+                }
+            }
+            if !phrase.trim().is_empty() {
+                tokens.push(Token::Term {
+                    term: Term::Phrase(phrase.trim().to_string()),
+                    is_negated,
+                });
+            }
+            continue;
+        }
+
+        let mut word = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            word.push(c);
+            chars.next();
+        }
+
+        if word == "OR" && !is_negated {
+            tokens.push(Token::Or);
+            continue;
+        }
+
+        match word.split_once(':') {
+            Some((name, value))
+                if OPERATORS.contains(&name.to_lowercase().as_str()) && !value.is_empty() =>
+            {
+                tokens.push(Token::Operator {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                    is_negated,
+                })
+            }
+            _ => tokens.push(Token::Term {
+                term: Term::Word(word),
+                is_negated,
+            }),
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> SearchQuery {
+        SearchQuery::parse(input).unwrap()
+    }
+
+    fn words(words: &[&str]) -> Vec<Vec<Term>> {
+        words
+            .iter()
+            .map(|word| vec![Term::Word(word.to_string())])
+            .collect()
+    }
+
+    fn date(date: &str) -> Option<NaiveDate> {
+        Some(NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap())
+    }
+
+    #[test]
+    fn words_must_all_match() {
+        assert_eq!(parse("do calculus").term_groups, words(&["do", "calculus"]));
+    }
+
+    #[test]
+    fn quoted_phrases_are_one_term() {
+        let query = parse(r#"counterfactual "do calculus""#);
+        assert_eq!(
+            query.term_groups,
+            vec![
+                vec![Term::Word("counterfactual".to_string())],
+                vec![Term::Phrase("do calculus".to_string())],
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_quotes_are_rejected() {
+        assert!(SearchQuery::parse(r#""do calculus"#).is_err());
+    }
+
+    #[test]
+    fn words_joined_by_or_form_a_group() {
+        assert_eq!(
+            parse("causal OR causality").term_groups,
+            vec![vec![
+                Term::Word("causal".to_string()),
+                Term::Word("causality".to_string()),
+            ]]
+        );
+        assert!(SearchQuery::parse("OR causal").is_err());
+        assert!(SearchQuery::parse("causal OR").is_err());
+    }
+
+    #[test]
+    fn dashes_exclude_terms() {
+        let query = parse(r#"causal -bayes -"p values" - done"#);
+        assert_eq!(query.term_groups, words(&["causal", "done"]));
+        assert_eq!(
+            query.excluded_terms,
+            vec![
+                Term::Word("bayes".to_string()),
+                Term::Phrase("p values".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn from_and_to_collect_handles() {
+        let query = parse("from:@yudapearl from:eliasbareinboim to:some_user");
+        assert_eq!(query.from, vec!["yudapearl", "eliasbareinboim"]);
+        assert_eq!(query.to, vec!["some_user"]);
+        assert!(query.term_groups.is_empty());
+    }
+
+    #[test]
+    fn since_and_until_take_dates() {
+        let query = parse("since:2020-01-01 UNTIL:2021-06-30");
+        assert_eq!(query.since, date("2020-01-01"));
+        assert_eq!(query.until, date("2021-06-30"));
+    }
+
+    #[test]
+    fn bad_dates_are_rejected() {
+        assert!(SearchQuery::parse("since:yesterday").is_err());
+        assert!(SearchQuery::parse("until:2020-13-01").is_err());
+    }
+
+    #[test]
+    fn filters_can_be_required_or_excluded() {
+        let query = parse("is:reply -is:thread has:links");
+        assert_eq!(
+            query.filters,
+            vec![
+                (Filter::Reply, true),
+                (Filter::Thread, false),
+                (Filter::Link, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_filters_are_rejected() {
+        assert!(SearchQuery::parse("is:retweet").is_err());
+        assert!(SearchQuery::parse("has:video").is_err());
+    }
+
+    #[test]
+    fn only_filters_can_be_excluded() {
+        assert!(SearchQuery::parse("-from:yudapearl").is_err());
+    }
+
+    #[test]
+    fn unknown_operators_are_words() {
+        let query = parse("Re:thing https://bayes.net -note:this");
+        assert_eq!(query.term_groups, words(&["Re:thing", "https://bayes.net"]));
+        assert_eq!(query.excluded_terms, vec![Term::Word("note:this".to_string())]);
+    }
+}
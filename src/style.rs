@@ -19,6 +19,19 @@ impl container::StyleSheet for App {
     }
 }
 
+pub struct Panel;
+
+impl container::StyleSheet for Panel {
+    fn style(&self) -> container::Style {
+        container::Style {
+            background: theme::CONTENT_BG_COLOR.into(),
+            text_color: theme::MAIN_TEXT_COLOR.into(),
+            border_radius: 10.0,
+            ..container::Style::default()
+        }
+    }
+}
+
 pub struct Tweet;
 
 impl button::StyleSheet for Tweet {
@@ -40,6 +53,65 @@ impl button::StyleSheet for Tweet {
     }
 }
 
+pub struct HighlightedTweet;
+
+impl button::StyleSheet for HighlightedTweet {
+    fn active(&self) -> button::Style {
+        button::Style {
+            border_width: 2.0,
+            border_color: theme::SELECTED_TWEET_BORDER_COLOR,
+            ..Tweet.active()
+        }
+    }
+
+    fn hovered(&self) -> button::Style {
+        button::Style {
+            border_width: 2.0,
+            border_color: theme::SELECTED_TWEET_BORDER_COLOR,
+            ..Tweet.hovered()
+        }
+    }
+}
+
+pub struct EmbeddedTweet;
+
+impl button::StyleSheet for EmbeddedTweet {
+    fn active(&self) -> button::Style {
+        button::Style {
+            background: theme::MAIN_BG_COLOR.into(),
+            border_width: 1.0,
+            border_color: theme::CONTENT_HIGHLIGHT_BG_COLOR,
+            ..Tweet.active()
+        }
+    }
+
+    fn hovered(&self) -> button::Style {
+        button::Style {
+            background: theme::CONTENT_HIGHLIGHT_BG_COLOR.into(),
+            ..self.active()
+        }
+    }
+}
+
+/// A clickable name or handle that reads as part of the surrounding text.
+pub struct Link;
+
+impl button::StyleSheet for Link {
+    fn active(&self) -> button::Style {
+        button::Style {
+            text_color: theme::LINK_TEXT_COLOR,
+            ..button::Style::default()
+        }
+    }
+
+    fn hovered(&self) -> button::Style {
+        button::Style {
+            text_color: theme::MAIN_TEXT_COLOR,
+            ..self.active()
+        }
+    }
+}
+
 pub struct NavButton;
 
 impl button::StyleSheet for NavButton {
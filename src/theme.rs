@@ -10,6 +10,10 @@ pub const BUTTON_INACTIVE_BG_COLOR: Color = colors::RAISIN_BLACK_TRANSPARENT;
 pub const CONTENT_HIGHLIGHT_BG_COLOR: Color = colors::RAISIN_BLACK_LIGHT;
 pub const BUTTON_HOVER_BG_COLOR: Color = CONTENT_HIGHLIGHT_BG_COLOR;
 pub const MAIN_BG_COLOR: Color = colors::RICH_BLACK;
+pub const SEARCH_HIGHLIGHT_TEXT_COLOR: Color = colors::MAIZE;
+pub const ERROR_TEXT_COLOR: Color = colors::TOMATO;
+pub const SELECTED_TWEET_BORDER_COLOR: Color = colors::MAIZE;
+pub const LINK_TEXT_COLOR: Color = colors::CELESTIAL_BLUE;
 mod colors {
     use iced::Color;
     pub const RAISIN_BLACK: Color = Color::from_rgb(0.12, 0.11, 0.12);
@@ -18,4 +22,7 @@ mod colors {
     pub const RICH_BLACK: Color = Color::from_rgb(0.047, 0.035, 0.0431);
     pub const CULTURED_WHITE: Color = Color::from_rgb(0.96, 0.956, 0.96);
     pub const CULTURED_WHITE_TRANSPARENT: Color = Color::from_rgba(0.96, 0.956, 0.96, 0.05);
+    pub const MAIZE: Color = Color::from_rgb(0.96, 0.8, 0.3);
+    pub const TOMATO: Color = Color::from_rgb(0.94, 0.36, 0.3);
+    pub const CELESTIAL_BLUE: Color = Color::from_rgb(0.29, 0.6, 0.86);
 }
@@ -1,5 +1,6 @@
 use chrono::{DateTime, FixedOffset};
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::{HashMap, HashSet};
 use time::{format_description, OffsetDateTime};
 use twitter_v2::{Tweet, User};
 
@@ -33,6 +34,21 @@ impl<T: Clone> SelectList<T> {
         self.selected = item;
     }
 
+    /// Swaps in `item` for every entry matching `is_replaced`, wherever it is
+    /// in the history.
+    pub fn replace(&mut self, is_replaced: impl Fn(&T) -> bool, item: T) {
+        for entry in self
+            .previous
+            .iter_mut()
+            .chain(std::iter::once(&mut self.selected))
+            .chain(self.next.iter_mut())
+        {
+            if is_replaced(entry) {
+                *entry = item.clone();
+            }
+        }
+    }
+
     pub fn new(item: T) -> SelectList<T> {
         SelectList {
             previous: Vec::<T>::new(),
@@ -45,7 +61,577 @@ impl<T: Clone> SelectList<T> {
 #[derive(Debug, Clone, PartialEq)]
 pub struct TweetData {
     pub tweet: Tweet,
-    pub user: UserData,
+    /// `None` when the author isn't archived and couldn't be fetched either
+    pub user: Option<UserData>,
+}
+
+/// A tweet placed in its conversation's reply tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversationNode {
+    pub tweet_data: TweetData,
+    /// How many replies deep the tweet sits, the conversation's first tweet is 0
+    pub depth: usize,
+    /// The archived tweet this one replies to, if any
+    pub parent_id: Option<u64>,
+    pub reply_count: usize,
+}
+
+/// A conversation rebuilt from its replied to references, listed depth first
+/// so every tweet directly precedes its replies. Replies come oldest first,
+/// and tweets answering something missing from the archive start their own
+/// branch at the top level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversationTree {
+    pub nodes: Vec<ConversationNode>,
+}
+
+impl ConversationTree {
+    pub fn new(conversation: &Vec<TweetData>) -> Self {
+        let tweet_ids: HashSet<u64> = conversation
+            .iter()
+            .map(|tweet_data| tweet_data.tweet.id.as_u64())
+            .collect();
+
+        let mut roots: Vec<&TweetData> = Vec::new();
+        let mut replies: HashMap<u64, Vec<&TweetData>> = HashMap::new();
+        for tweet_data in conversation {
+            let parent_id = tweet_data
+                .tweet
+                .referenced_tweets
+                .iter()
+                .flatten()
+                .find(|reference| reference.kind == ReferencedTweetKind::RepliedTo)
+                .map(|reference| reference.id.as_u64())
+                .filter(|parent_id| tweet_ids.contains(parent_id));
+            match parent_id {
+                Some(parent_id) => replies.entry(parent_id).or_default().push(tweet_data),
+                None => roots.push(tweet_data),
+            }
+        }
+
+        let by_created_at =
+            |a: &&TweetData, b: &&TweetData| a.tweet.created_at.cmp(&b.tweet.created_at);
+        roots.sort_by(by_created_at);
+        for siblings in replies.values_mut() {
+            siblings.sort_by(by_created_at);
+        }
+
+        let mut nodes = Vec::new();
+        let mut stack: Vec<(&TweetData, usize, Option<u64>)> =
+            roots.into_iter().rev().map(|root| (root, 0, None)).collect();
+        while let Some((tweet_data, depth, parent_id)) = stack.pop() {
+            let tweet_replies = replies
+                .get(&tweet_data.tweet.id.as_u64())
+                .cloned()
+                .unwrap_or_default();
+            nodes.push(ConversationNode {
+                tweet_data: tweet_data.clone(),
+                depth,
+                parent_id,
+                reply_count: tweet_replies.len(),
+            });
+            let tweet_id = Some(tweet_data.tweet.id.as_u64());
+            stack.extend(
+                tweet_replies
+                    .into_iter()
+                    .rev()
+                    .map(|reply| (reply, depth + 1, tweet_id)),
+            );
+        }
+
+        Self { nodes }
+    }
+
+    /// The self-reply chain running through a tweet, where its author kept
+    /// answering their own previous tweet. Where the author replied to
+    /// themselves more than once, the earliest reply continues the thread.
+    /// Single tweets aren't threads and give `None`.
+    pub fn self_thread(&self, tweet_id: u64) -> Option<UnrolledThread> {
+        let node_by_id = |id: u64| {
+            self.nodes
+                .iter()
+                .find(|node| node.tweet_data.tweet.id.as_u64() == id)
+        };
+        let mut first = node_by_id(tweet_id)?;
+        // a thread is read as its author's writing, which needs an author
+        first.tweet_data.user.as_ref()?;
+        let author_id = first.tweet_data.tweet.author_id;
+
+        while let Some(parent) = first.parent_id.and_then(node_by_id) {
+            if parent.tweet_data.tweet.author_id != author_id {
+                break;
+            }
+            first = parent;
+        }
+
+        let mut tweets = vec![first.tweet_data.clone()];
+        let mut current_id = first.tweet_data.tweet.id.as_u64();
+        // nodes are depth first with replies oldest first, so the first match is
+        // the earliest self-reply
+        while let Some(next) = self.nodes.iter().find(|node| {
+            node.parent_id == Some(current_id) && node.tweet_data.tweet.author_id == author_id
+        }) {
+            tweets.push(next.tweet_data.clone());
+            current_id = next.tweet_data.tweet.id.as_u64();
+        }
+
+        match tweets.len() {
+            1 => None,
+            _ => Some(UnrolledThread { tweets }),
+        }
+    }
+
+    /// The nodes left showing once the replies under every collapsed tweet are
+    /// hidden.
+    pub fn visible_nodes(&self, collapsed_tweet_ids: &HashSet<u64>) -> Vec<&ConversationNode> {
+        let mut visible_nodes = Vec::new();
+        let mut collapsed_depth: Option<usize> = None;
+        for node in &self.nodes {
+            match collapsed_depth {
+                Some(depth) if node.depth > depth => continue,
+                _ => collapsed_depth = None,
+            }
+            if collapsed_tweet_ids.contains(&node.tweet_data.tweet.id.as_u64()) {
+                collapsed_depth = Some(node.depth);
+            }
+            visible_nodes.push(node);
+        }
+        visible_nodes
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThreadFormat {
+    PlainText,
+    Markdown,
+}
+
+impl ThreadFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ThreadFormat::PlainText => "txt",
+            ThreadFormat::Markdown => "md",
+        }
+    }
+}
+
+/// A thread of self-replies read as one piece of writing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnrolledThread {
+    pub tweets: Vec<TweetData>,
+}
+
+impl UnrolledThread {
+    pub fn author(&self) -> &UserData {
+        self.tweets[0]
+            .user
+            .as_ref()
+            .expect("Threads are only unrolled for archived authors")
+    }
+
+    /// Each tweet's text with thread numbering such as "1/" or "(2/5)" removed,
+    /// as the unrolled thread reads on without it.
+    pub fn paragraphs(&self) -> Vec<String> {
+        self.tweets
+            .iter()
+            .map(|tweet_data| {
+                strip_thread_numbering(&decode_html_entities(&tweet_data.tweet.text))
+            })
+            .collect()
+    }
+
+    pub fn render(&self, format: ThreadFormat) -> String {
+        match format {
+            ThreadFormat::PlainText => self.to_plain_text(),
+            ThreadFormat::Markdown => self.to_markdown(),
+        }
+    }
+
+    /// Where `export` writes the thread, named after its author and first tweet.
+    pub fn export_file_name(&self, format: ThreadFormat) -> String {
+        format!(
+            "thread-{}-{}.{}",
+            self.author().twitter_handle,
+            self.tweets[0].tweet.id.as_u64(),
+            format.extension()
+        )
+    }
+
+    pub fn export(&self, format: ThreadFormat) -> std::io::Result<String> {
+        let file_name = self.export_file_name(format);
+        std::fs::write(&file_name, self.render(format))?;
+        Ok(file_name)
+    }
+
+    pub fn to_plain_text(&self) -> String {
+        let author = self.author();
+        format!(
+            "Thread by {} (@{})\n\n{}\n\n{}\n",
+            author.name,
+            author.twitter_handle,
+            self.paragraphs().join("\n\n"),
+            self.source_url()
+        )
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let author = self.author();
+        let format = format_description::parse("[year]-[month]-[day]").expect("failed to get format");
+        let posted_on = self.tweets[0]
+            .tweet
+            .created_at
+            .map(|created_at| created_at.format(&format).expect("Failed to format datetime"))
+            .unwrap_or_default();
+        format!(
+            "# Thread by {} (@{})\n\n*{} tweets, posted on {}*\n\n{}\n\n[Read on Twitter]({})\n",
+            author.name,
+            author.twitter_handle,
+            self.tweets.len(),
+            posted_on,
+            self.paragraphs().join("\n\n"),
+            self.source_url()
+        )
+    }
+
+    fn source_url(&self) -> String {
+        format!(
+            "https://twitter.com/{}/status/{}",
+            self.author().twitter_handle,
+            self.tweets[0].tweet.id.as_u64()
+        )
+    }
+}
+
+fn is_thread_number(word: &str) -> bool {
+    let word = word.trim_start_matches('(').trim_end_matches(')');
+    match word.split_once('/') {
+        Some((position, total)) => {
+            !position.is_empty()
+                && position.chars().all(|c| c.is_ascii_digit())
+                && total.chars().all(|c| c.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+fn strip_thread_numbering(text: &str) -> String {
+    let mut text = text.trim();
+    if let Some((first_word, rest)) = text.split_once(char::is_whitespace) {
+        if is_thread_number(first_word) {
+            text = rest.trim_start();
+        }
+    }
+    if let Some((rest, last_word)) = text.rsplit_once(char::is_whitespace) {
+        if is_thread_number(last_word) {
+            text = rest.trim_end();
+        }
+    }
+    text.to_string()
+}
+
+/// A run of tweet text, or a part of it that opens something when clicked.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TweetTextSegment {
+    Text(String),
+    /// A handle mentioned with @, without the @
+    Mention(String),
+    /// A hashtag without the #
+    Hashtag(String),
+    /// A link shown the way Twitter displays it, opening the full URL
+    Link { display_url: String, url: String },
+}
+
+impl TweetTextSegment {
+    pub fn display_text(&self) -> String {
+        match self {
+            TweetTextSegment::Text(text) => text.clone(),
+            TweetTextSegment::Mention(twitter_handle) => format!("@{}", twitter_handle),
+            TweetTextSegment::Hashtag(tag) => format!("#{}", tag),
+            TweetTextSegment::Link { display_url, .. } => display_url.clone(),
+        }
+    }
+}
+
+/// What a tweet's stored entities say about its text. Tweets archived without
+/// entities fall back to spotting mentions and hashtags in the text itself.
+struct TextEntities {
+    /// Each t.co link paired with its display form and the URL it expands to
+    urls: HashMap<String, (String, String)>,
+    hashtags: Option<HashSet<String>>,
+    mentions: Option<HashSet<String>>,
+}
+
+impl TextEntities {
+    fn from_tweet(tweet: &Tweet) -> Self {
+        let entities = tweet.entities.as_ref();
+        let urls = entities
+            .and_then(|entities| entities.urls.as_ref())
+            .into_iter()
+            .flatten()
+            .map(|url_entity| {
+                let url = url_entity.url.to_string();
+                let expanded_url = url_entity
+                    .expanded_url
+                    .as_ref()
+                    .map(|expanded_url| expanded_url.to_string())
+                    .unwrap_or_else(|| url.clone());
+                let display_url = url_entity
+                    .display_url
+                    .clone()
+                    .unwrap_or_else(|| expanded_url.clone());
+                (url, (display_url, expanded_url))
+            })
+            .collect();
+        let hashtags = entities.map(|entities| {
+            entities
+                .hashtags
+                .iter()
+                .flatten()
+                .map(|hashtag| hashtag.tag.to_lowercase())
+                .collect()
+        });
+        let mentions = entities.map(|entities| {
+            entities
+                .mentions
+                .iter()
+                .flatten()
+                .map(|mention| mention.username.to_lowercase())
+                .collect()
+        });
+        Self {
+            urls,
+            hashtags,
+            mentions,
+        }
+    }
+
+    fn is_hashtag(&self, tag: &str) -> bool {
+        match &self.hashtags {
+            Some(hashtags) => hashtags.contains(&tag.to_lowercase()),
+            None => !tag.chars().all(|c| c.is_ascii_digit()),
+        }
+    }
+
+    fn is_mention(&self, twitter_handle: &str) -> bool {
+        match &self.mentions {
+            Some(mentions) => mentions.contains(&twitter_handle.to_lowercase()),
+            None => true,
+        }
+    }
+
+    fn link(&self, url: &str) -> TweetTextSegment {
+        let (display_url, url) = match self.urls.get(url) {
+            Some((display_url, expanded_url)) => (display_url.clone(), expanded_url.clone()),
+            None => (
+                url.split_once("://").map_or(url, |(_, rest)| rest).to_string(),
+                url.to_string(),
+            ),
+        };
+        TweetTextSegment::Link { display_url, url }
+    }
+}
+
+/// Breaks a tweet's text into lines of at most `line_length` characters,
+/// wrapping between words. HTML entities are decoded, t.co links shown the way
+/// Twitter displays them, and links, mentions and hashtags split out into their
+/// own segments. Line breaks in the tweet are kept.
+pub fn tweet_text_lines(tweet: &Tweet, line_length: usize) -> Vec<Vec<TweetTextSegment>> {
+    let entities = TextEntities::from_tweet(tweet);
+    let mut lines = Vec::new();
+    for paragraph in decode_html_entities(&tweet.text).split('\n') {
+        let mut line: Vec<TweetTextSegment> = Vec::new();
+        let mut current_length = 0;
+        for word in paragraph.split(' ') {
+            let segments = word_segments(word, &entities);
+            let word_length: usize = segments
+                .iter()
+                .map(|segment| segment.display_text().chars().count())
+                .sum();
+            if current_length > 0 && current_length + 1 + word_length > line_length {
+                lines.push(std::mem::take(&mut line));
+                current_length = 0;
+            }
+            if current_length > 0 {
+                push_text(&mut line, " ");
+                current_length += 1;
+            }
+            for segment in segments {
+                match segment {
+                    TweetTextSegment::Text(text) => push_text(&mut line, &text),
+                    segment => line.push(segment),
+                }
+            }
+            current_length += word_length;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+fn push_text(line: &mut Vec<TweetTextSegment>, text: &str) {
+    match line.last_mut() {
+        Some(TweetTextSegment::Text(last)) => last.push_str(text),
+        _ => line.push(TweetTextSegment::Text(text.to_string())),
+    }
+}
+
+/// Splits the links, mentions and hashtags out of a word such as
+/// "(@yudapearl)". An @ or # following a letter or digit is part of something
+/// else, such as an email address.
+fn word_segments(word: &str, entities: &TextEntities) -> Vec<TweetTextSegment> {
+    for (i, c) in word.char_indices() {
+        let rest = &word[i..];
+        let (segment, remainder) = if rest.starts_with("https://") || rest.starts_with("http://") {
+            // a link runs to the end of the word, less any closing punctuation
+            let link = rest.trim_end_matches(|c: char| ".,;:!?)\"'".contains(c));
+            (entities.link(link), &rest[link.len()..])
+        } else if (c == '@' || c == '#')
+            && !word[..i].chars().last().map_or(false, char::is_alphanumeric)
+        {
+            let name = &rest[1..];
+            let name_length = name
+                .find(|name_char: char| match c {
+                    '@' => !(name_char.is_ascii_alphanumeric() || name_char == '_'),
+                    _ => !(name_char.is_alphanumeric() || name_char == '_'),
+                })
+                .unwrap_or(name.len());
+            let (name, remainder) = name.split_at(name_length);
+            match c {
+                '@' if !name.is_empty() && entities.is_mention(name) => {
+                    (TweetTextSegment::Mention(name.to_string()), remainder)
+                }
+                '#' if !name.is_empty() && entities.is_hashtag(name) => {
+                    (TweetTextSegment::Hashtag(name.to_string()), remainder)
+                }
+                _ => continue,
+            }
+        } else {
+            continue;
+        };
+
+        let mut segments = Vec::new();
+        if i > 0 {
+            segments.push(TweetTextSegment::Text(word[..i].to_string()));
+        }
+        segments.push(segment);
+        segments.extend(word_segments(remainder, entities));
+        return segments;
+    }
+
+    match word.is_empty() {
+        true => Vec::new(),
+        false => vec![TweetTextSegment::Text(word.to_string())],
+    }
+}
+
+/// Tweet text comes with &, < and > escaped as HTML entities.
+pub fn decode_html_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| decode_html_entity(&rest[1..end]).map(|c| (c, end)));
+        match entity {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_html_entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => {
+            let code = match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => name.strip_prefix('#').and_then(|decimal| decimal.parse().ok()),
+            };
+            code.and_then(char::from_u32)
+        }
+    }
+}
+
+/// Delimit the matched terms inside search snippets. Control characters are
+/// used as they never occur in tweet text.
+pub const SNIPPET_HIGHLIGHT_START: char = '\u{1}';
+pub const SNIPPET_HIGHLIGHT_END: char = '\u{2}';
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub tweet_data: TweetData,
+    pub snippet: Option<String>,
+}
+
+impl SearchResult {
+    /// Splits the snippet into runs of text, flagging the runs that matched
+    /// the query.
+    pub fn snippet_segments(&self) -> Vec<(String, bool)> {
+        let snippet = match &self.snippet {
+            Some(snippet) => snippet,
+            None => return Vec::new(),
+        };
+
+        let mut segments = Vec::new();
+        let mut current = String::new();
+        let mut is_highlighted = false;
+        for c in snippet.chars() {
+            if c == SNIPPET_HIGHLIGHT_START || c == SNIPPET_HIGHLIGHT_END {
+                if !current.is_empty() {
+                    segments.push((decode_html_entities(&current), is_highlighted));
+                    current.clear();
+                }
+                is_highlighted = c == SNIPPET_HIGHLIGHT_START;
+            } else if c == '\n' {
+                current.push(' ');
+            } else {
+                current.push(c);
+            }
+        }
+        if !current.is_empty() {
+            segments.push((decode_html_entities(&current), is_highlighted));
+        }
+        segments
+    }
+}
+
+/// How many rows a bulk write added, changed and left alone, with the tweets
+/// it couldn't write because their author isn't archived.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WriteReport {
+    pub inserted: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub without_author: Vec<Tweet>,
+}
+
+impl std::fmt::Display for WriteReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} inserted, {} updated, {} unchanged, {} without an archived author",
+            self.inserted,
+            self.updated,
+            self.unchanged,
+            self.without_author.len()
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -58,10 +644,27 @@ impl TweetData {
     pub fn new(user: &User, tweet: Tweet) -> Self {
         Self {
             tweet: tweet,
-            user: UserData {
+            user: Some(UserData {
                 twitter_handle: user.username.clone(),
                 name: user.name.clone(),
-            },
+            }),
+        }
+    }
+
+    /// For a tweet whose author isn't archived and couldn't be fetched either.
+    pub fn with_missing_author(tweet: Tweet) -> Self {
+        Self {
+            tweet: tweet,
+            user: None,
+        }
+    }
+
+    /// The author's `@handle` for labels, worded to fit the same sentences when
+    /// the author is missing.
+    pub fn author_label(&self) -> String {
+        match &self.user {
+            Some(user) => format!("@{}", user.twitter_handle),
+            None => "an unknown author".to_string(),
         }
     }
 
@@ -93,6 +696,19 @@ pub fn to_ron<T: ?Sized + Serialize>(item: &T) -> String {
     ron::ser::to_string_pretty(item, ron::ser::PrettyConfig::new())
         .expect("Failed to parse tweet into string")
 }
+
+pub fn to_ron_column<T: Serialize>(item: &Option<T>) -> Option<String> {
+    item.as_ref().map(|item| {
+        ron::ser::to_string(item).expect("Failed to serialize field into Rusty Object Notation")
+    })
+}
+
+pub fn from_ron_column<T: DeserializeOwned>(column: &Option<String>) -> Option<T> {
+    column.as_ref().map(|column_ron| {
+        ron::from_str(column_ron)
+            .expect("Failed to parse column from Rusty Object Notation")
+    })
+}
 #[derive(Debug, Serialize)]
 pub struct TweetReferenceData {
     pub reference_type: ReferencedTweetKind,
@@ -102,7 +718,11 @@ pub struct TweetReferenceData {
 
 impl TweetReferenceData {
     pub fn type_to_string(&self) -> String {
-        match self.reference_type {
+        Self::kind_to_string(&self.reference_type)
+    }
+
+    pub fn kind_to_string(kind: &ReferencedTweetKind) -> String {
+        match kind {
             ReferencedTweetKind::RepliedTo => "replied_to",
             ReferencedTweetKind::Retweeted => "retweeted",
             ReferencedTweetKind::Quoted => "quoted",
@@ -139,3 +759,175 @@ impl TweetReferenceData {
         }
     }
 }
+
+/// An account the archive follows, with how much of it has been archived.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedAccountData {
+    pub user: User,
+    pub tweet_count: usize,
+    pub added_at: DateTime<FixedOffset>,
+    pub last_synced_at: Option<DateTime<FixedOffset>>,
+}
+
+/// The archived tweets a tweet points at and the ones pointing back at it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TweetReferencesData {
+    pub replying_to: Option<TweetData>,
+    pub quoting: Option<TweetData>,
+    pub retweet_of: Option<TweetData>,
+    pub replies: Vec<TweetData>,
+    pub quotes: Vec<TweetData>,
+    pub retweets: Vec<TweetData>,
+}
+
+impl TweetReferencesData {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserSnapshotData {
+    pub user_id: i64,
+    pub fetched_at: DateTime<FixedOffset>,
+    pub name: String,
+    pub username: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    pub url: Option<String>,
+    pub profile_image_url: Option<String>,
+    pub followers_count: Option<i64>,
+    pub following_count: Option<i64>,
+    pub tweet_count: Option<i64>,
+    pub listed_count: Option<i64>,
+    pub verified: Option<bool>,
+    pub protected: Option<bool>,
+}
+
+impl UserSnapshotData {
+    pub fn from_user(user: &User, fetched_at: DateTime<FixedOffset>) -> Self {
+        let metric = |count: usize| -> Option<i64> { count.try_into().ok() };
+        Self {
+            user_id: user
+                .id
+                .as_u64()
+                .try_into()
+                .expect("Failed to parse i64 from u64"),
+            fetched_at,
+            name: user.name.clone(),
+            username: user.username.clone(),
+            description: user.description.clone(),
+            location: user.location.clone(),
+            url: user.url.as_ref().map(|url| url.to_string()),
+            profile_image_url: user.profile_image_url.as_ref().map(|url| url.to_string()),
+            followers_count: user
+                .public_metrics
+                .as_ref()
+                .and_then(|metrics| metric(metrics.followers_count)),
+            following_count: user
+                .public_metrics
+                .as_ref()
+                .and_then(|metrics| metric(metrics.following_count)),
+            tweet_count: user
+                .public_metrics
+                .as_ref()
+                .and_then(|metrics| metric(metrics.tweet_count)),
+            listed_count: user
+                .public_metrics
+                .as_ref()
+                .and_then(|metrics| metric(metrics.listed_count)),
+            verified: user.verified,
+            protected: user.protected,
+        }
+    }
+
+    /// Compares everything except when the snapshot was taken.
+    pub fn is_same_profile(&self, other: &Self) -> bool {
+        Self {
+            fetched_at: other.fetched_at,
+            ..self.clone()
+        } == *other
+    }
+
+    /// Human readable descriptions of what changed since `previous`.
+    pub fn changes_since(&self, previous: &Self) -> Vec<String> {
+        let fields = [
+            ("Name", Some(previous.name.clone()), Some(self.name.clone())),
+            (
+                "Username",
+                Some(format!("@{}", previous.username)),
+                Some(format!("@{}", self.username)),
+            ),
+            ("Bio", previous.description.clone(), self.description.clone()),
+            ("Location", previous.location.clone(), self.location.clone()),
+            ("Website", previous.url.clone(), self.url.clone()),
+            (
+                "Profile image",
+                previous.profile_image_url.clone(),
+                self.profile_image_url.clone(),
+            ),
+            (
+                "Followers",
+                previous.followers_count.map(|count| count.to_string()),
+                self.followers_count.map(|count| count.to_string()),
+            ),
+            (
+                "Following",
+                previous.following_count.map(|count| count.to_string()),
+                self.following_count.map(|count| count.to_string()),
+            ),
+            (
+                "Tweets",
+                previous.tweet_count.map(|count| count.to_string()),
+                self.tweet_count.map(|count| count.to_string()),
+            ),
+            (
+                "Listed",
+                previous.listed_count.map(|count| count.to_string()),
+                self.listed_count.map(|count| count.to_string()),
+            ),
+            (
+                "Verified",
+                previous.verified.map(|verified| verified.to_string()),
+                self.verified.map(|verified| verified.to_string()),
+            ),
+            (
+                "Protected",
+                previous.protected.map(|protected| protected.to_string()),
+                self.protected.map(|protected| protected.to_string()),
+            ),
+        ];
+
+        fields
+            .into_iter()
+            .filter(|(_, before, after)| before != after)
+            .map(|(label, before, after)| {
+                format!(
+                    "{}: {} → {}",
+                    label,
+                    before.unwrap_or_else(|| "(none)".to_string()),
+                    after.unwrap_or_else(|| "(none)".to_string())
+                )
+            })
+            .collect()
+    }
+
+    /// Describes the first archived version of a profile.
+    pub fn summary(&self) -> Vec<String> {
+        let mut summary = vec![format!("{} (@{})", self.name, self.username)];
+        if let Some(description) = &self.description {
+            summary.push(format!("Bio: {}", description));
+        }
+        if let Some(location) = &self.location {
+            summary.push(format!("Location: {}", location));
+        }
+        if let (Some(followers), Some(following)) = (self.followers_count, self.following_count)
+        {
+            summary.push(format!(
+                "{} followers, {} following",
+                followers, following
+            ));
+        }
+        summary
+    }
+}